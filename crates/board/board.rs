@@ -1,16 +1,75 @@
+use crate::game::{GameResult, Termination};
 use crate::piece::{
-    ChoiceOfPromotablePiece, Color, DiagonalRange, HorizontalRange, Move, Piece, PieceType,
-    Position, VerticalRange, XAxis, YAxis,
+    ChoiceOfPromotablePiece, Color, DiagonalRange, HorizontalRange, Move, MoveRecord, Piece,
+    PieceType, Position, VerticalRange, XAxis, YAxis,
 };
-use crate::pieces::{Bishop, King, Knight, Pawn, Queen, Rook};
+use crate::pieces::{
+    Archbishop, Bishop, Chancellor, FairyPiece, King, Knight, Pawn, Promoted, Queen, Rook,
+};
+use crate::rules::{Rules, Variant};
 use crate::Effect;
 use crate::{BottomLeft, BottomRight, UpperLeft, UpperRight};
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Most files and ranks a board can have. Files are named by a single letter
+pub const MAX_SIZE: u8 = 26;
+
+/// Rank of the square counted from the side of the given color, starting at 0. The corner is
+/// the upper right one of the board
+pub(crate) fn relative_rank(color: Color, position: Position, corner: Position) -> i8 {
+    match color.forward() {
+        (0, 1) => position.y.0,
+        (0, _) => corner.y.0 - position.y.0,
+        (1, _) => position.x.0,
+        _ => corner.x.0 - position.x.0,
+    }
+}
+
+/// Position of the color in the turn order of four-player chess
+fn turn_order(color: Color) -> usize {
+    Color::FOUR_PLAYERS
+        .iter()
+        .position(|player| *player == color)
+        .unwrap_or_default()
+}
+
+/// Where a king and a rook go when castling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Castling {
+    /// Origin and destination of the king
+    pub king: (Position, Position),
+    /// Origin and destination of the rook
+    pub rook: (Position, Position),
+}
+
+#[derive(Clone)]
 pub struct Board {
     pieces: Vec<Box<dyn Piece>>,
+    /// Position of the upper right corner
     dimensions: (XAxis, YAxis),
     turn: Color,
+    rules: Rules,
+    /// Half moves since the last capture or pawn move
+    halfmove_clock: u32,
+    /// Starts at 1 and goes up after every move of black
+    fullmove_number: u32,
+    /// Pieces each player can drop on the board, only used when the rules allow drops
+    pockets: BTreeMap<Color, Vec<PieceType>>,
+    /// Checks given by each player
+    checks: BTreeMap<Color, u32>,
+    /// Square skipped by a pawn that advanced two squares on the last move
+    en_passant: Option<Position>,
+    /// The last move played, which leads back to the ones before it. Clones share it, so
+    /// cloning a board doesn't copy its history
+    history: Option<Arc<PlayedMove>>,
+}
+
+/// A move executed on the board, with the board as it was before the move
+struct PlayedMove {
+    record: MoveRecord,
+    before: Board,
 }
 
 impl Board {
@@ -18,12 +77,240 @@ impl Board {
     /// Only intended for testing
     /// TODO: Add a "fromstr" new where you draw the board with a string
     fn new(pieces: Vec<Box<dyn Piece>>) -> Self {
-        let dimensions = (XAxis::new(7), YAxis::new(7));
+        Board::with_dimensions(pieces, 8, 8)
+    }
+
+    pub fn with_dimensions(pieces: Vec<Box<dyn Piece>>, width: u8, height: u8) -> Self {
+        Board::with_rules(pieces, width, height, Rules::default())
+    }
+
+    pub fn with_rules(pieces: Vec<Box<dyn Piece>>, width: u8, height: u8, rules: Rules) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "The board needs at least one square"
+        );
+        assert!(
+            width <= MAX_SIZE && height <= MAX_SIZE,
+            "The board can't be larger than {MAX_SIZE}x{MAX_SIZE}"
+        );
+        let dimensions = (XAxis::new(width as i8 - 1), YAxis::new(height as i8 - 1));
 
         Board {
             pieces,
             dimensions,
             turn: Color::White,
+            rules,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            pockets: BTreeMap::new(),
+            checks: BTreeMap::new(),
+            en_passant: None,
+            history: None,
+        }
+    }
+
+    /// Fischer Random chess. The back rank is chosen from the Scharnagl number of the position,
+    /// between 0 and 959. 518 is the standard setup
+    pub fn chess960(index: u16) -> Self {
+        assert!(index < 960, "There are only 960 starting positions");
+        use PieceType::*;
+
+        let mut back_rank: [Option<PieceType>; 8] = [None; 8];
+        let mut index = index as usize;
+
+        // Bishops go on squares of different colors
+        back_rank[(index % 4) * 2 + 1] = Some(Bishop);
+        index /= 4;
+        back_rank[(index % 4) * 2] = Some(Bishop);
+        index /= 4;
+
+        // The rest of the pieces are placed on the empty squares, from left to right
+        let mut place = |nth_empty: usize, piece: PieceType| {
+            let square = back_rank
+                .iter()
+                .enumerate()
+                .filter(|(_, square)| square.is_none())
+                .nth(nth_empty)
+                .map(|(x, _)| x)
+                .unwrap();
+            back_rank[square] = Some(piece);
+        };
+        place(index % 6, Queen);
+        index /= 6;
+
+        let knights = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        let (first, second) = knights[index];
+        // The first knight takes an empty square, so the second one moves one to the left
+        place(first, Knight);
+        place(second - 1, Knight);
+
+        // The king always ends up between the rooks
+        place(0, Rook);
+        place(0, King);
+        place(0, Rook);
+
+        let back_rank: Vec<_> = back_rank.into_iter().map(Option::unwrap).collect();
+        Board::with_dimensions(Board::mirrored_setup(&back_rank, 8), 8, 8)
+    }
+
+    /// Standard setup where captured pieces can be dropped back on the board
+    pub fn crazyhouse() -> Self {
+        Board::with_variant(Variant::Crazyhouse)
+    }
+
+    /// Standard setup where captures explode
+    pub fn atomic() -> Self {
+        Board::with_variant(Variant::Atomic)
+    }
+
+    /// Starting position and rules of the given variant
+    pub fn with_variant(variant: Variant) -> Self {
+        let Some(fen) = variant.starting_position() else {
+            return Board::four_player();
+        };
+        Board::from_fen_with_rules(fen, variant.rules()).expect("Invalid starting position")
+    }
+
+    /// Capablanca chess: 10x8 with an archbishop and a chancellor for each player
+    pub fn capablanca() -> Self {
+        Board::with_variant(Variant::Capablanca)
+    }
+
+    /// Grand chess: 10x10 with an archbishop and a chancellor for each player. Promotion is
+    /// simplified, see Variant::Grand
+    pub fn grand() -> Self {
+        Board::with_variant(Variant::Grand)
+    }
+
+    /// Four-player chess: 14x14 without the 3x3 corners. Red plays from the bottom, blue from the
+    /// left, yellow from the top and green from the right, in that order
+    pub fn four_player() -> Self {
+        use PieceType::*;
+        let queen_left = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+        let king_left = [Rook, Knight, Bishop, King, Queen, Bishop, Knight, Rook];
+        // Back rank, with the square of the first piece and the direction the rank goes in
+        let setups = [
+            (Color::Red, queen_left, (3, 0), (1, 0)),
+            (Color::Blue, king_left, (0, 3), (0, 1)),
+            (Color::Yellow, king_left, (3, 13), (1, 0)),
+            (Color::Green, queen_left, (13, 3), (0, 1)),
+        ];
+
+        let mut pieces = Vec::new();
+        for (color, back_rank, (x, y), (dx, dy)) in setups {
+            let (fx, fy) = color.forward();
+            for (i, piece) in back_rank.into_iter().enumerate() {
+                let (x, y) = (x + dx * i as i8, y + dy * i as i8);
+                let square = Position::new(XAxis::new(x), YAxis::new(y));
+                let front = Position::new(XAxis::new(x + fx), YAxis::new(y + fy));
+                pieces.extend(Board::new_piece(piece, color, square));
+                pieces.extend(Board::new_piece(Pawn, color, front));
+            }
+        }
+
+        let mut board = Board::with_rules(pieces, 14, 14, Variant::FourPlayer.rules());
+        board.set_state(Color::Red, 0, 1);
+        board
+    }
+
+    /// Los Alamos chess: 6x6 without bishops
+    pub fn los_alamos() -> Self {
+        use PieceType::*;
+        let pieces = Board::mirrored_setup(&[Rook, Knight, Queen, King, Knight, Rook], 6);
+        let rules = Rules {
+            promotions: vec![
+                ChoiceOfPromotablePiece::Queen,
+                ChoiceOfPromotablePiece::Rook,
+                ChoiceOfPromotablePiece::Knight,
+            ],
+            ..Rules::minichess()
+        };
+        Board::with_rules(pieces, 6, 6, rules)
+    }
+
+    /// Gardner minichess: 5x5 with one of each piece
+    pub fn gardner() -> Self {
+        use PieceType::*;
+        let pieces = Board::mirrored_setup(&[Rook, Knight, Bishop, Queen, King], 5);
+        Board::with_rules(pieces, 5, 5, Rules::minichess())
+    }
+
+    /// 10x8 board with the standard pieces and an extra pair of knights
+    pub fn ten_by_eight() -> Self {
+        use PieceType::*;
+        #[rustfmt::skip]
+        let back_rank = [Rook, Knight, Knight, Bishop, Queen, King, Bishop, Knight, Knight, Rook];
+        let pieces = Board::mirrored_setup(&back_rank, 8);
+        Board::with_dimensions(pieces, 10, 8)
+    }
+
+    /// Both players get the same back rank and a row of pawns in front of it
+    fn mirrored_setup(back_rank: &[PieceType], height: u8) -> Vec<Box<dyn Piece>> {
+        let last = height as i8 - 1;
+        let rows = [(Color::White, 0, 1), (Color::Black, last, last - 1)];
+
+        rows.into_iter()
+            .flat_map(|(color, back, front)| {
+                back_rank.iter().enumerate().flat_map(move |(x, piece)| {
+                    let x = XAxis::new(x as i8);
+                    [
+                        Board::new_piece(*piece, color, Position::new(x, YAxis::new(back))),
+                        Board::new_piece(
+                            PieceType::Pawn,
+                            color,
+                            Position::new(x, YAxis::new(front)),
+                        ),
+                    ]
+                    .into_iter()
+                    .flatten()
+                })
+            })
+            .collect()
+    }
+
+    /// None for fairy pieces, which need the moves the rules give them. See create_piece
+    pub fn new_piece(piece: PieceType, color: Color, position: Position) -> Option<Box<dyn Piece>> {
+        let piece: Box<dyn Piece> = match piece {
+            PieceType::Bishop => Box::new(Bishop::new(color, position)),
+            PieceType::King => Box::new(King::new(color, position)),
+            PieceType::Knight => Box::new(Knight::new(color, position)),
+            PieceType::Pawn => Box::new(Pawn::new(color, position)),
+            PieceType::Queen => Box::new(Queen::new(color, position)),
+            PieceType::Rook => Box::new(Rook::new(color, position)),
+            PieceType::Archbishop => Box::new(Archbishop::new(color, position)),
+            PieceType::Chancellor => Box::new(Chancellor::new(color, position)),
+            PieceType::Fairy(_) => return None,
+        };
+        Some(piece)
+    }
+
+    /// Like new_piece, but it also knows how the fairy pieces of the rules move
+    pub fn create_piece(
+        &self,
+        piece: PieceType,
+        color: Color,
+        position: Position,
+    ) -> Box<dyn Piece> {
+        match piece {
+            PieceType::Fairy(letter) => {
+                let betza =
+                    self.rules.fairy_pieces.get(&letter).unwrap_or_else(|| {
+                        panic!("the rules don't define the fairy piece {letter}")
+                    });
+                Box::new(FairyPiece::new(letter, betza.clone(), color, position))
+            }
+            _ => Board::new_piece(piece, color, position).expect("Not a fairy piece"),
         }
     }
 
@@ -31,10 +318,140 @@ impl Board {
         self.turn
     }
 
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Rank of the square counted from the side of the given color, starting at 0
+    pub fn relative_rank(&self, color: Color, position: Position) -> i8 {
+        let (_, _, _, ur) = self.get_limits();
+        relative_rank(color, position, ur.0)
+    }
+
+    /// Pawns of the given color are promoted when they reach this square
+    pub fn is_promotion_square(&self, color: Color, position: Position) -> bool {
+        let rank = self.relative_rank(color, position);
+        match self.rules.promotion_rank {
+            Some(promotion) => rank == promotion as i8 - 1,
+            None => {
+                let (width, height) = self.get_dimensions();
+                let last = if color.forward().0 == 0 {
+                    height
+                } else {
+                    width
+                };
+                rank == last as i8 - 1
+            }
+        }
+    }
+
+    /// Meant to be used right after loading a position, to choose the variant being played
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn get_fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Pieces the given color can drop on the board
+    pub fn get_pocket(&self, color: Color) -> &[PieceType] {
+        self.pockets.get(&color).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn add_to_pocket(&mut self, color: Color, piece: PieceType) {
+        self.pockets.entry(color).or_default().push(piece);
+    }
+
+    /// Takes a piece of the given type out of the pocket. Returns false if there was none
+    pub fn remove_from_pocket(&mut self, color: Color, piece: PieceType) -> bool {
+        let Some(pocket) = self.pockets.get_mut(&color) else {
+            return false;
+        };
+        match pocket.iter().rposition(|pocketed| *pocketed == piece) {
+            Some(index) => {
+                pocket.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Times the given color has given check
+    pub fn get_checks(&self, color: Color) -> u32 {
+        self.checks.get(&color).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn set_checks(&mut self, color: Color, checks: u32) {
+        self.checks.insert(color, checks);
+    }
+
+    /// Square where a pawn that advanced two squares on the last move can be captured en passant
+    pub fn get_en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    pub(crate) fn set_en_passant(&mut self, square: Option<Position>) {
+        self.en_passant = square;
+    }
+
+    /// The en passant square, if the player to move can actually capture on it
+    pub(crate) fn en_passant_capture(&self) -> Option<Position> {
+        self.en_passant?;
+        self.get_legal_moves()
+            .into_iter()
+            .find(|mov| matches!(mov.effect, Some(Effect::EnPassant(_))))
+            .map(|mov| mov.destination)
+    }
+
+    /// Square of the enemy pawn that the given color can capture en passant, if any
+    pub(crate) fn en_passant_victim(&self, color: Color) -> Option<Position> {
+        let square = self.en_passant?;
+        self.find_pieces(Some(PieceType::Pawn), None)
+            .filter(|pawn| pawn.get_color() != color)
+            .find(|pawn| {
+                // The pawn stands right past the square it skipped
+                let (dx, dy) = pawn.get_color().forward();
+                pawn.get_position() == Position::new(square.x + dx.into(), square.y + dy.into())
+            })
+            .map(|pawn| pawn.get_position())
+    }
+
+    /// The squares in the middle of the board, d4, e4, d5 and e5 in a standard board
+    pub fn center(&self) -> [Position; 4] {
+        let (width, height) = self.get_dimensions();
+        let (x, y) = ((width / 2) as i8, (height / 2) as i8);
+        [(x - 1, y - 1), (x, y - 1), (x - 1, y), (x, y)]
+            .map(|(x, y)| Position::new(x.into(), y.into()))
+    }
+
+    /// Width and height of the board, in squares
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        (
+            (self.dimensions.0 .0 + 1) as u8,
+            (self.dimensions.1 .0 + 1) as u8,
+        )
+    }
+
     pub fn get_pieces(&self) -> impl Iterator<Item = &Box<dyn Piece>> {
         self.pieces.iter()
     }
 
+    pub(crate) fn get_pieces_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Piece>> {
+        self.pieces.iter_mut()
+    }
+
+    /// Side to move and move counters, used when loading a position
+    pub(crate) fn set_state(&mut self, turn: Color, halfmove_clock: u32, fullmove_number: u32) {
+        self.turn = turn;
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number;
+    }
+
     pub fn get_limits(&self) -> (BottomLeft, BottomRight, UpperLeft, UpperRight) {
         let bl = BottomLeft(Position {
             x: XAxis::new(0i8),
@@ -67,6 +484,14 @@ impl Board {
         let within_x = bl.0.x <= pos.x && pos.x <= br.0.x;
         let within_y = bl.0.y <= pos.y && pos.y <= ul.0.y;
 
+        // The 3x3 corners are cut out of the four-player board
+        if self.rules.variant == Variant::FourPlayer {
+            let in_corner = |value: i8, last: i8| value < 3 || value > last - 3;
+            if in_corner(pos.x.0, br.0.x.0) && in_corner(pos.y.0, ul.0.y.0) {
+                return false;
+            }
+        }
+
         within_x && within_y
     }
 
@@ -387,84 +812,682 @@ impl Board {
         self.get_pieces()
             .find(|piece| piece.get_position() == pos)
             .filter(|piece| piece.get_color() == self.get_turn())
-            .map(|piece| piece.available_positions(self))
+            .map(|_| {
+                // Whether a move is legal can depend on the moves of the other pieces
+                self.get_legal_moves()
+                    .into_iter()
+                    .filter(|mov| mov.origin == pos)
+                    .collect()
+            })
     }
 
-    pub fn execute_move(&mut self, mov: Move) {
-        // TODO: Remove unwrap(s)
+    /// Every move the player whose turn it is can make
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let moves: Vec<_> = self
+            .find_pieces(None, Some(self.get_turn()))
+            .flat_map(|piece| piece.available_positions(self))
+            .chain(self.drops(self.get_turn()))
+            .filter(|mov| self.is_legal(*mov))
+            .collect();
+
+        // In Antichess, captures are compulsory
+        if self.rules.variant == Variant::Antichess && moves.iter().any(|mov| self.is_capture(*mov))
+        {
+            return moves
+                .into_iter()
+                .filter(|mov| self.is_capture(*mov))
+                .collect();
+        }
+        moves
+    }
+
+    /// The move takes an enemy piece. Promotions can capture as well
+    fn is_capture(&self, mov: Move) -> bool {
+        match mov.effect {
+            Some(Effect::Capture) | Some(Effect::EnPassant(_)) => true,
+            Some(Effect::Promotion(_)) => self
+                .get_pieces()
+                .any(|piece| piece.get_position() == mov.destination),
+            _ => false,
+        }
+    }
+
+    /// Drops available to the given color, without checking if they leave the king in check
+    fn drops(&self, color: Color) -> Vec<Move> {
+        if !self.rules.drops {
+            return Vec::new();
+        }
+
+        let mut pocket = self.get_pocket(color).to_vec();
+        pocket.sort();
+        pocket.dedup();
+
+        let occupied: HashSet<_> = self
+            .get_pieces()
+            .map(|piece| piece.get_position())
+            .collect();
+        let (_, _, _, ur) = self.get_limits();
+        let empty: Vec<_> = (0..=ur.0.y.0)
+            .flat_map(|y| (0..=ur.0.x.0).map(move |x| Position::new(x.into(), y.into())))
+            .filter(|square| !occupied.contains(square))
+            .collect();
+
+        pocket
+            .into_iter()
+            .flat_map(|piece| {
+                empty
+                    .iter()
+                    // Pawns can't be dropped on the first or the last rank
+                    .filter(move |square| {
+                        piece != PieceType::Pawn || (square.y.0 != 0 && square.y != ur.0.y)
+                    })
+                    .map(move |square| Move::new(*square, *square, Some(Effect::Drop(piece))))
+            })
+            .collect()
+    }
+
+    /// Returns true if any opponent piece can capture the king of the given color
+    pub fn is_in_check(&self, color: Color) -> bool {
+        // The king is an ordinary piece in Antichess, and can be captured in Fog of war
+        if matches!(self.rules.variant, Variant::Antichess | Variant::FogOfWar) {
+            return false;
+        }
+
+        let kings: HashSet<_> = self
+            .find_pieces(Some(PieceType::King), Some(color))
+            .map(|king| king.get_position())
+            .collect();
+        if kings.is_empty() {
+            return false;
+        }
+
+        // In Atomic, kings can't capture, and a king next to the enemy king can't be captured
+        // since both would explode
+        let atomic = self.rules.variant == Variant::Atomic;
+        if atomic
+            && self
+                .find_pieces(Some(PieceType::King), Some(!color))
+                .any(|enemy| {
+                    kings
+                        .iter()
+                        .any(|king| is_adjacent(*king, enemy.get_position()))
+                })
+        {
+            return false;
+        }
+
+        self.get_pieces()
+            .filter(|piece| piece.get_color() != color)
+            .filter(|piece| !atomic || piece.get_type() != PieceType::King)
+            .flat_map(|piece| piece.available_positions(self))
+            .any(|mov| kings.contains(&mov.destination))
+    }
+
+    /// A move is legal if it doesn't leave the king of the player that made it in check
+    fn is_legal(&self, mov: Move) -> bool {
+        let color = self.get_turn();
+        let mut board = self.clone();
+
+        // The chosen piece doesn't change whether the king is left in check
+        let mov = match mov.effect {
+            Some(Effect::Promotion(None)) => Move::new(
+                mov.origin,
+                mov.destination,
+                Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Queen))),
+            ),
+            _ => mov,
+        };
+        board.apply_move(mov);
+
+        if self.rules.variant == Variant::Atomic {
+            // The king would explode along with the piece it captures
+            let captured = self.is_capture(mov);
+            let is_king = self
+                .find_pieces(Some(PieceType::King), None)
+                .any(|king| king.get_position() == mov.origin);
+            if captured && is_king {
+                return false;
+            }
+
+            // Blowing up the enemy king wins straight away, as long as the own king survives
+            let has_king = |color| {
+                board
+                    .find_pieces(Some(PieceType::King), Some(color))
+                    .next()
+                    .is_some()
+            };
+            if !has_king(color) {
+                return false;
+            }
+            if !has_king(!color) {
+                return true;
+            }
+        }
+
+        if board.is_in_check(color) {
+            return false;
+        }
+        // Neither king can be put in check in Racing Kings
+        if self.rules.variant == Variant::RacingKings && board.is_in_check(!color) {
+            return false;
+        }
+
+        // The king can't castle out of, or through, a square where it would be in check
+        if let Some(castling) = self.as_castling(mov) {
+            let (origin, destination) = castling.king;
+            let direction = (destination.x.0 - origin.x.0).signum();
+            let mut square = origin;
+            loop {
+                // The rook is taken out of the way, the path is otherwise empty
+                let mut board = self.clone();
+                board
+                    .pieces
+                    .retain(|piece| piece.get_position() != castling.rook.0);
+                let king = board
+                    .pieces
+                    .iter_mut()
+                    .find(|piece| piece.get_position() == origin)
+                    .unwrap();
+                king.move_to(square);
+                if board.is_in_check(color) {
+                    return false;
+                }
+
+                if square == destination {
+                    break;
+                }
+                square.x += direction.into();
+            }
+        }
+
+        true
+    }
+
+    fn as_castling(&self, mov: Move) -> Option<Castling> {
+        let Some(Effect::Castling {
+            origin,
+            destination,
+        }) = mov.effect
+        else {
+            return None;
+        };
+        let moved = (mov.origin, mov.destination);
+        let other = (origin, destination);
+
+        let is_king = self
+            .find_pieces(Some(PieceType::King), None)
+            .any(|king| king.get_position() == mov.origin);
+        if is_king {
+            Some(Castling {
+                king: moved,
+                rook: other,
+            })
+        } else {
+            Some(Castling {
+                king: other,
+                rook: moved,
+            })
+        }
+    }
+
+    /// Castlings available to the given color. The king always ends up next to the corner, on
+    /// the file of the knight, and the rook on the other side of the king. This also covers
+    /// Chess960, where kings and rooks start on other squares
+    pub(crate) fn castlings(&self, color: Color) -> Vec<Castling> {
+        if !self.rules.castling {
+            return Vec::new();
+        }
+
+        let kings: Vec<_> = self
+            .find_pieces(Some(PieceType::King), Some(color))
+            .collect();
+        let [king] = kings[..] else {
+            return Vec::new();
+        };
+        if king.was_moved() {
+            return Vec::new();
+        }
+        let king_origin = king.get_position();
+        let (_, br, _, _) = self.get_limits();
+
+        self.find_pieces(Some(PieceType::Rook), Some(color))
+            .filter(|rook| !rook.was_moved())
+            .map(|rook| rook.get_position())
+            .filter(|rook| rook.y == king_origin.y)
+            .filter_map(|rook_origin| {
+                let (king_x, rook_x) = if rook_origin.x > king_origin.x {
+                    (br.0.x - XAxis::new(1), br.0.x - XAxis::new(2))
+                } else {
+                    (XAxis::new(2), XAxis::new(3))
+                };
+                let king_destination = Position::new(king_x, king_origin.y);
+                let rook_destination = Position::new(rook_x, king_origin.y);
+
+                // Every square the king and the rook go through has to be empty
+                let xs = [king_origin.x, king_x, rook_origin.x, rook_x];
+                let from = xs.iter().min().unwrap().0;
+                let to = xs.iter().max().unwrap().0;
+                let blocked = self.get_pieces().any(|piece| {
+                    let position = piece.get_position();
+                    position.y == king_origin.y
+                        && (from..=to).contains(&position.x.0)
+                        && position != king_origin
+                        && position != rook_origin
+                });
+
+                (!blocked).then_some(Castling {
+                    king: (king_origin, king_destination),
+                    rook: (rook_origin, rook_destination),
+                })
+            })
+            .collect()
+    }
+
+    /// Squares the given color can see in Fog of war: the ones its pieces stand on, and the ones
+    /// they can move to
+    pub fn visible_squares(&self, color: Color) -> HashSet<Position> {
+        self.find_pieces(None, Some(color))
+            .flat_map(|piece| {
+                piece
+                    .available_positions(self)
+                    .into_iter()
+                    .map(|mov| mov.destination)
+                    .chain([piece.get_position()])
+            })
+            .filter(|square| self.is_inside(square))
+            .collect()
+    }
+
+    /// The board as seen by the given color, without the enemy pieces it can't see
+    pub fn view_for(&self, color: Color) -> Board {
+        let visible = self.visible_squares(color);
+        let mut view = self.clone();
+        view.pieces
+            .retain(|piece| visible.contains(&piece.get_position()));
+        // The opponent's pocket is hidden as well, and so are the moves that were played
+        view.pockets.remove(&!color);
+        view.history = None;
+        view
+    }
+
+    /// Returns the result if the player whose turn it is has no legal moves left
+    pub fn outcome(&self) -> Option<(GameResult, Termination)> {
+        // Four-player games go on after a player is out, FourPlayerGame keeps track of them
+        if self.rules.variant == Variant::FourPlayer {
+            return None;
+        }
+
+        if let Some(outcome) = self.variant_outcome() {
+            return Some(outcome);
+        }
 
-        // TODO: Do this in one step; i'm doing it in two because of compilaton error
+        if !self.get_legal_moves().is_empty() {
+            return None;
+        }
+
+        // Running out of moves is the goal of Antichess
+        if self.rules.variant == Variant::Antichess {
+            let termination = if self.find_pieces(None, Some(self.turn)).next().is_none() {
+                Termination::AllPiecesLost
+            } else {
+                Termination::Stalemate
+            };
+            return GameResult::win_for(self.turn).map(|result| (result, termination));
+        }
+
+        if self.is_in_check(self.turn) {
+            GameResult::win_for(!self.turn).map(|result| (result, Termination::Checkmate))
+        } else {
+            Some((GameResult::Draw, Termination::Stalemate))
+        }
+    }
+
+    /// Ways of ending the game that only some variants have. Only the player that just moved can
+    /// have won this way
+    fn variant_outcome(&self) -> Option<(GameResult, Termination)> {
+        let winner = GameResult::win_for(!self.turn)?;
+        let king = |color| self.find_pieces(Some(PieceType::King), Some(color)).next();
+
+        match self.rules.variant {
+            Variant::Atomic => king(self.turn)
+                .is_none()
+                .then_some((winner, Termination::Explosion)),
+            Variant::ThreeCheck => {
+                (self.get_checks(!self.turn) >= 3).then_some((winner, Termination::ThreeChecks))
+            }
+            Variant::KingOfTheHill => {
+                let center = self.center();
+                king(!self.turn)
+                    .is_some_and(|king| center.contains(&king.get_position()))
+                    .then_some((winner, Termination::KingOfTheHill))
+            }
+            Variant::RacingKings => self.race_outcome(),
+            // Black has to capture every white piece
+            Variant::Horde => self
+                .find_pieces(None, Some(Color::White))
+                .next()
+                .is_none()
+                .then_some((GameResult::BlackWins, Termination::AllPiecesLost)),
+            Variant::FogOfWar => king(self.turn)
+                .is_none()
+                .then_some((winner, Termination::KingCaptured)),
+            Variant::Standard
+            | Variant::Crazyhouse
+            | Variant::Bughouse
+            | Variant::Antichess
+            | Variant::Capablanca
+            | Variant::Grand
+            | Variant::FourPlayer => None,
+        }
+    }
+
+    /// The first king on the last rank wins, but black gets one more move to reach it after
+    /// white does, which ends in a draw
+    fn race_outcome(&self) -> Option<(GameResult, Termination)> {
+        let (_, _, ul, _) = self.get_limits();
+        let finished = |color| {
+            self.find_pieces(Some(PieceType::King), Some(color))
+                .any(|king| king.get_position().y == ul.0.y)
+        };
+
+        match (finished(Color::White), finished(Color::Black)) {
+            (true, true) => Some((GameResult::Draw, Termination::RaceFinished)),
+            (false, true) => Some((GameResult::BlackWins, Termination::RaceFinished)),
+            (true, false) if self.turn == Color::White => {
+                Some((GameResult::WhiteWins, Termination::RaceFinished))
+            }
+            (true, false) => {
+                let kings: Vec<_> = self
+                    .find_pieces(Some(PieceType::King), Some(Color::Black))
+                    .map(|king| king.get_position())
+                    .collect();
+                let black_can_finish = self
+                    .get_legal_moves()
+                    .into_iter()
+                    .any(|mov| kings.contains(&mov.origin) && mov.destination.y == ul.0.y);
+                (!black_can_finish).then_some((GameResult::WhiteWins, Termination::RaceFinished))
+            }
+            (false, false) => None,
+        }
+    }
+
+    pub fn execute_move(&mut self, mov: Move) -> MoveRecord {
+        let before = self.clone();
+        let mut record = self.apply_move(mov);
+
+        record.check = self.is_in_check(self.turn);
+        record.checkmate = record.check && self.get_legal_moves().is_empty();
+        if record.check {
+            *self.checks.entry(record.color).or_default() += 1;
+        }
+
+        self.history = Some(Arc::new(PlayedMove {
+            record: record.clone(),
+            before,
+        }));
+        record
+    }
+
+    /// Takes back the last move executed, leaving the board exactly as it was before it.
+    /// Returns None if no moves were executed since the position was set up
+    pub fn undo(&mut self) -> Option<MoveRecord> {
+        let played = self.history.take()?;
+        let record = played.record.clone();
+        *self = match Arc::try_unwrap(played) {
+            Ok(played) => played.before,
+            Err(played) => played.before.clone(),
+        };
+        Some(record)
+    }
+
+    /// Moves executed since the position was set up, from the first to the last
+    pub fn get_history(&self) -> Vec<MoveRecord> {
+        let mut records = Vec::new();
+        let mut played = self.history.as_deref();
+        while let Some(PlayedMove { record, before }) = played {
+            records.push(record.clone());
+            played = before.history.as_deref();
+        }
+        records.reverse();
+        records
+    }
+
+    /// Moves the pieces around without checking the state of the game afterwards
+    fn apply_move(&mut self, mov: Move) -> MoveRecord {
+        if let Some(Effect::Drop(piece)) = mov.effect {
+            return self.apply_drop(piece, mov);
+        }
+
+        // TODO: Remove unwrap(s)
         let index = self
             .pieces
             .iter()
             .position(|piece| piece.get_position() == mov.origin)
             .unwrap();
 
-        let enemy_piece = self
-            .pieces
-            .iter()
-            .position(|piece| piece.get_position() == mov.destination);
-
-        let piece: &mut Box<dyn Piece> = self.pieces.get_mut(index).unwrap();
-
         // TODO: Add error
-        let piece_color = piece.get_color();
+        let piece_color = self.pieces[index].get_color();
         if piece_color != self.turn {
             panic!()
         }
 
+        // Promotions can also capture the piece standing on the last lane
+        let enemy_piece = match mov.effect {
+            Some(Effect::Capture) => Some(
+                self.pieces
+                    .iter()
+                    .position(|piece| piece.get_position() == mov.destination)
+                    .expect(
+                        "Tried to capture an enemy at position, but there is no enemy at that position",
+                    ),
+            ),
+            Some(Effect::Promotion(_)) => self.pieces.iter().position(|piece| {
+                piece.get_position() == mov.destination && piece.get_color() != piece_color
+            }),
+            Some(Effect::EnPassant(victim)) => Some(
+                self.pieces
+                    .iter()
+                    .position(|piece| piece.get_position() == victim)
+                    .expect("Tried to capture en passant, but there is no pawn to capture"),
+            ),
+            _ => None,
+        };
+
+        // The other piece is found before anything moves, the king could end up on its square
+        let other_piece_index = match mov.effect {
+            Some(Effect::Castling { origin, .. }) => Some(
+                self.pieces
+                    .iter()
+                    .position(|piece| piece.get_position() == origin)
+                    .unwrap(),
+            ),
+            _ => None,
+        };
+
+        let mut record = MoveRecord {
+            mov,
+            piece: self.pieces[index].get_type(),
+            color: piece_color,
+            captured: enemy_piece.map(|enemy| {
+                let enemy = &self.pieces[enemy];
+                (enemy.get_type(), enemy.get_color())
+            }),
+            exploded: Vec::new(),
+            promotion: None,
+            castling: None,
+            check: false,
+            checkmate: false,
+        };
+
+        let piece: &mut Box<dyn Piece> = self.pieces.get_mut(index).unwrap();
         piece.move_to(mov.destination);
 
         match mov.effect {
-            Some(Effect::Capture) => {
-                let enemy_piece = enemy_piece.expect(
-                    "Tried to capture an enemy at position, but there is no enemy at that position",
-                );
-                self.pieces.swap_remove(enemy_piece);
-            }
             Some(Effect::Castling {
                 origin,
                 destination,
             }) => {
-                let other_piece_index = self
-                    .pieces
-                    .iter()
-                    .position(|piece| piece.get_position() == origin)
-                    .unwrap();
-
                 let other_piece: &mut Box<dyn Piece> =
-                    self.pieces.get_mut(other_piece_index).unwrap();
+                    self.pieces.get_mut(other_piece_index.unwrap()).unwrap();
 
                 other_piece.move_to(destination);
+                record.castling = Some((origin, destination));
             }
             Some(Effect::Promotion(choice)) => {
                 if let Some(choice) = choice {
-                    let promoted_piece = Board::promote_piece(choice, piece.as_ref());
-                    *piece = promoted_piece;
+                    let (color, position) = (piece.get_color(), piece.get_position());
+                    let promoted_piece = self.promote_piece(choice, color, position);
+                    self.pieces[index] = promoted_piece;
+                    record.promotion = Some(choice);
                 } else {
                     panic!("Tried to promote piece but no piece was specified");
                 }
             }
-            None => (),
+            Some(Effect::Capture) | Some(Effect::EnPassant(_)) | Some(Effect::Drop(_)) | None => (),
+        }
+
+        // Removed last so that the indexes above stay valid
+        if let Some(enemy_piece) = enemy_piece {
+            let captured = self.pieces.swap_remove(enemy_piece);
+            if self.rules.drops {
+                // Promoted pieces go back to being pawns
+                let piece = if captured.was_promoted() {
+                    PieceType::Pawn
+                } else {
+                    captured.get_type()
+                };
+                self.add_to_pocket(piece_color, piece);
+            }
+            if self.rules.variant == Variant::Atomic {
+                record.exploded = self.explode(mov.destination);
+            }
         }
 
-        self.turn = !self.turn;
+        self.end_turn(&record);
+        record
     }
-    // TODO: Pub crate instead of pub
-    pub fn capture_piece(&mut self, pos: Position) {
-        // Retain pieces that have a different position
+
+    fn apply_drop(&mut self, piece: PieceType, mov: Move) -> MoveRecord {
+        let color = self.turn;
+        let pocket = self.pockets.entry(color).or_default();
+        let index = pocket
+            .iter()
+            .position(|pocketed| *pocketed == piece)
+            .expect("Tried to drop a piece that isn't in the pocket");
+        pocket.remove(index);
+
+        let mut dropped = self.create_piece(piece, color, mov.destination);
+        // Pawns dropped on their starting rank can still advance two squares
+        dropped.set_moved(self.relative_rank(color, mov.destination) != 1);
+        self.pieces.push(dropped);
+
+        let record = MoveRecord {
+            mov,
+            piece,
+            color,
+            captured: None,
+            exploded: Vec::new(),
+            promotion: None,
+            castling: None,
+            check: false,
+            checkmate: false,
+        };
+        self.end_turn(&record);
+        record
+    }
+
+    /// Removes the piece on the given square, and every piece around it except for pawns
+    fn explode(&mut self, center: Position) -> Vec<(PieceType, Color, Position)> {
+        let mut exploded = Vec::new();
+        self.pieces.retain(|piece| {
+            let position = piece.get_position();
+            let kept = position != center
+                && (piece.get_type() == PieceType::Pawn || !is_adjacent(position, center));
+            if !kept {
+                exploded.push((piece.get_type(), piece.get_color(), position));
+            }
+            kept
+        });
+        exploded
+    }
+
+    /// Updates the move counters and gives the turn to the other player
+    fn end_turn(&mut self, record: &MoveRecord) {
+        if record.piece == PieceType::Pawn || record.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        // Pawns that advance two squares can be captured on the square they skipped, but only
+        // right away
+        let (origin, destination) = (record.mov.origin, record.mov.destination);
+        let (x, y) = (destination.x.0 - origin.x.0, destination.y.0 - origin.y.0);
+        let double_step = record.piece == PieceType::Pawn
+            && record.mov.effect.is_none()
+            && (x.abs() == 2 && y == 0 || x == 0 && y.abs() == 2);
+        self.en_passant = double_step
+            .then(|| Position::new(origin.x + (x / 2).into(), origin.y + (y / 2).into()));
+        let next = self.next_player(self.turn);
+        let round_over = match self.rules.variant {
+            Variant::FourPlayer => turn_order(next) <= turn_order(self.turn),
+            _ => self.turn == Color::Black,
+        };
+        if round_over {
+            self.fullmove_number += 1;
+        }
+        self.turn = next;
+    }
+
+    /// Player that moves after the given one. In four-player chess, players without a king are
+    /// skipped
+    fn next_player(&self, color: Color) -> Color {
+        if self.rules.variant != Variant::FourPlayer {
+            return !color;
+        }
+        let current = turn_order(color);
+        (1..=Color::FOUR_PLAYERS.len())
+            .map(|offset| Color::FOUR_PLAYERS[(current + offset) % Color::FOUR_PLAYERS.len()])
+            .find(|player| {
+                self.find_pieces(Some(PieceType::King), Some(*player))
+                    .next()
+                    .is_some()
+            })
+            .unwrap_or(color)
+    }
+
+    /// Takes a player out of a four-player game, removing all of their pieces
+    pub(crate) fn eliminate(&mut self, color: Color) {
+        self.pieces.retain(|piece| piece.get_color() != color);
+        if self.turn == color {
+            self.turn = self.next_player(color);
+        }
+    }
+
+    // TODO: Pub crate instead of pub
+    pub fn capture_piece(&mut self, pos: Position) {
+        // Retain pieces that have a different position
         self.pieces.retain(|piece| piece.get_position() != pos);
     }
 
-    fn promote_piece(choice: ChoiceOfPromotablePiece, original: &dyn Piece) -> Box<dyn Piece> {
-        let position = original.get_position();
-        let color = original.get_color();
-        match choice {
+    fn promote_piece(
+        &self,
+        choice: ChoiceOfPromotablePiece,
+        color: Color,
+        position: Position,
+    ) -> Box<dyn Piece> {
+        let piece: Box<dyn Piece> = match choice {
             ChoiceOfPromotablePiece::Bishop => Box::new(Bishop::new(color, position)),
             ChoiceOfPromotablePiece::Knight => Box::new(Knight::new(color, position)),
             ChoiceOfPromotablePiece::Queen => Box::new(Queen::new(color, position)),
             ChoiceOfPromotablePiece::Rook => Box::new(Rook::new(color, position)),
-        }
+            ChoiceOfPromotablePiece::King => Box::new(King::new(color, position)),
+            ChoiceOfPromotablePiece::Archbishop => Box::new(Archbishop::new(color, position)),
+            ChoiceOfPromotablePiece::Chancellor => Box::new(Chancellor::new(color, position)),
+            ChoiceOfPromotablePiece::Fairy(letter) => {
+                self.create_piece(PieceType::Fairy(letter), color, position)
+            }
+        };
+        Box::new(Promoted::new(piece))
     }
 
     // TODO: Use this function for the other functions for the differnet pieces.
@@ -491,6 +1514,11 @@ impl Board {
     }
 }
 
+/// The squares touch each other, including diagonally
+fn is_adjacent(a: Position, b: Position) -> bool {
+    a != b && (a.x.0 - b.x.0).abs() <= 1 && (a.y.0 - b.y.0).abs() <= 1
+}
+
 impl Default for Board {
     fn default() -> Self {
         let dimensions = (XAxis::new(7), YAxis::new(7));
@@ -540,6 +1568,13 @@ impl Default for Board {
             pieces,
             dimensions,
             turn: Color::White,
+            rules: Rules::default(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            pockets: BTreeMap::new(),
+            checks: BTreeMap::new(),
+            en_passant: None,
+            history: None,
         }
     }
 }
@@ -990,4 +2025,763 @@ mod tests {
             .unwrap();
         assert_eq!(moves.len(), 2);
     }
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for text in moves {
+            board.execute_move(board.parse_move(text).unwrap());
+        }
+    }
+
+    #[test]
+    fn en_passant_test() {
+        let mut board = Board::default();
+        play(&mut board, &["e2e4", "a7a6", "e4e5", "d7d5"]);
+        assert_eq!(board.get_en_passant(), Some(square(3, 5)));
+
+        let mov = board.parse_move("e5d6").unwrap();
+        assert_eq!(mov.effect, Some(Effect::EnPassant(square(3, 4))));
+        let record = board.execute_move(mov);
+        assert_eq!(record.captured, Some((PieceType::Pawn, Color::Black)));
+        assert_eq!(board.get_en_passant(), None);
+        assert!(board
+            .get_pieces()
+            .all(|piece| piece.get_position() != square(3, 4)));
+
+        board.undo();
+        assert!(board
+            .get_pieces()
+            .any(|piece| piece.get_position() == square(3, 4)));
+    }
+
+    #[test]
+    fn en_passant_only_right_away_test() {
+        let mut board = Board::default();
+        play(
+            &mut board,
+            &["e2e4", "d7d5", "e4e5", "f7f5", "b1c3", "b8c6"],
+        );
+        // Only the last double step can be captured
+        assert!(board.parse_move("e5f6").is_err());
+        assert!(board.parse_move("e5d6").is_err());
+        // Single steps can't be captured either
+        play(&mut board, &["a2a3", "d5d4", "a3a4", "d4d3"]);
+        assert_eq!(board.get_en_passant(), None);
+    }
+
+    #[test]
+    fn move_record_capture_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(Knight::new(Color::White, Position::new(XAxis::new(1), YAxis::new(0)))),
+            Box::new(Bishop::new(Color::Black, Position::new(XAxis::new(2), YAxis::new(2)))),
+        ];
+        let mut board = Board::new(pieces);
+
+        let mov = board
+            .get_moves_from(Position::new(1i8.into(), 0i8.into()))
+            .unwrap()
+            .into_iter()
+            .find(|mov| mov.destination == Position::new(2.into(), 2.into()))
+            .unwrap();
+
+        let record = board.execute_move(mov);
+
+        assert_eq!(record.piece, PieceType::Knight);
+        assert_eq!(record.color, Color::White);
+        assert_eq!(record.captured, Some((PieceType::Bishop, Color::Black)));
+        assert_eq!(record.promotion, None);
+        assert!(record.castling.is_none());
+        assert!(!record.check);
+    }
+
+    #[test]
+    fn move_record_promotion_capture_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(Pawn::new(Color::White, Position::new(XAxis::new(0), YAxis::new(6)))),
+            Box::new(Rook::new(Color::Black, Position::new(XAxis::new(0), YAxis::new(7)))),
+            Box::new(Rook::new(Color::Black, Position::new(XAxis::new(1), YAxis::new(7)))),
+        ];
+        let mut board = Board::new(pieces);
+
+        let mut mov = board
+            .get_moves_from(Position::new(0i8.into(), 6i8.into()))
+            .unwrap()
+            .into_iter()
+            .find(|mov| mov.destination == Position::new(1.into(), 7.into()))
+            .unwrap();
+        mov.effect = Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Knight)));
+
+        let record = board.execute_move(mov);
+
+        assert_eq!(record.piece, PieceType::Pawn);
+        assert_eq!(record.captured, Some((PieceType::Rook, Color::Black)));
+        assert_eq!(record.promotion, Some(ChoiceOfPromotablePiece::Knight));
+        assert_eq!(board.get_pieces().count(), 2);
+    }
+
+    #[test]
+    fn move_record_castling_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(Rook::new(Color::White, Position::new(XAxis::new(7), YAxis::new(0)))),
+            Box::new(King::new(Color::White, Position::new(XAxis::new(4), YAxis::new(0)))),
+        ];
+        let mut board = Board::new(pieces);
+
+        let mov = board
+            .get_moves_from(Position::new(4i8.into(), 0i8.into()))
+            .unwrap()
+            .into_iter()
+            .find(|mov| matches!(mov.effect, Some(Effect::Castling { .. })))
+            .unwrap();
+
+        let record = board.execute_move(mov);
+
+        assert_eq!(record.piece, PieceType::King);
+        assert_eq!(
+            record.castling,
+            Some((
+                Position::new(7.into(), 0.into()),
+                Position::new(5.into(), 0.into())
+            ))
+        );
+    }
+
+    #[test]
+    fn move_record_checkmate_test() {
+        // Back rank mate: the rook goes to the last lane
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(King::new(Color::White, Position::new(XAxis::new(6), YAxis::new(0)))),
+            Box::new(Rook::new(Color::White, Position::new(XAxis::new(0), YAxis::new(0)))),
+            Box::new(King::new(Color::Black, Position::new(XAxis::new(6), YAxis::new(7)))),
+            Box::new(Pawn::new(Color::Black, Position::new(XAxis::new(5), YAxis::new(6)))),
+            Box::new(Pawn::new(Color::Black, Position::new(XAxis::new(6), YAxis::new(6)))),
+            Box::new(Pawn::new(Color::Black, Position::new(XAxis::new(7), YAxis::new(6)))),
+        ];
+        let mut board = Board::new(pieces);
+
+        let mov = board
+            .get_moves_from(Position::new(0i8.into(), 0i8.into()))
+            .unwrap()
+            .into_iter()
+            .find(|mov| mov.destination == Position::new(0.into(), 7.into()))
+            .unwrap();
+
+        let record = board.execute_move(mov);
+
+        assert!(record.check);
+        assert!(record.checkmate);
+        assert!(board.is_in_check(Color::Black));
+        assert!(board.get_legal_moves().is_empty());
+    }
+
+    #[test]
+    fn pinned_piece_cant_move_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(King::new(Color::White, Position::new(XAxis::new(4), YAxis::new(0)))),
+            Box::new(Knight::new(Color::White, Position::new(XAxis::new(4), YAxis::new(1)))),
+            Box::new(Rook::new(Color::Black, Position::new(XAxis::new(4), YAxis::new(7)))),
+        ];
+        let board = Board::new(pieces);
+
+        let moves = board
+            .get_moves_from(Position::new(4i8.into(), 1i8.into()))
+            .unwrap();
+
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn moved_king_can_still_move_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(King::new(Color::White, Position::new(XAxis::new(4), YAxis::new(0)))),
+            Box::new(King::new(Color::Black, Position::new(XAxis::new(4), YAxis::new(7)))),
+        ];
+        let mut board = Board::new(pieces);
+
+        let mov = board
+            .get_moves_from(Position::new(4i8.into(), 0i8.into()))
+            .unwrap()
+            .into_iter()
+            .find(|mov| mov.destination == Position::new(4.into(), 1.into()))
+            .unwrap();
+        board.execute_move(mov);
+        board.execute_move(Move::new(
+            Position::new(4.into(), 7.into()),
+            Position::new(4.into(), 6.into()),
+            None,
+        ));
+
+        let moves = board
+            .get_moves_from(Position::new(4i8.into(), 1i8.into()))
+            .unwrap();
+        assert!(!moves.is_empty());
+    }
+
+    #[test]
+    fn gardner_test() {
+        let board = Board::gardner();
+
+        assert_eq!(board.get_dimensions(), (5, 5));
+        assert_eq!(board.get_pieces().count(), 20);
+        assert!(board.is_inside(&Position::new(4.into(), 4.into())));
+        assert!(!board.is_inside(&Position::new(5.into(), 4.into())));
+
+        // Pawns can only move one square
+        let moves = board
+            .get_moves_from(Position::new(0i8.into(), 1i8.into()))
+            .unwrap();
+        assert_eq!(moves.len(), 1);
+
+        let king = board
+            .find_pieces(Some(PieceType::King), Some(Color::Black))
+            .next()
+            .unwrap();
+        assert_eq!(king.get_position(), Position::new(4.into(), 4.into()));
+    }
+
+    #[test]
+    fn los_alamos_test() {
+        let board = Board::los_alamos();
+
+        assert_eq!(board.get_dimensions(), (6, 6));
+        assert_eq!(board.find_pieces(Some(PieceType::Bishop), None).count(), 0);
+        assert!(!board
+            .get_rules()
+            .promotions
+            .contains(&ChoiceOfPromotablePiece::Bishop));
+        assert_eq!(board.get_legal_moves().len(), 10);
+    }
+
+    #[test]
+    fn los_alamos_no_castling_test() {
+        #[rustfmt::skip]
+        let pieces: Vec<Box<dyn Piece>> = vec![
+            Box::new(Rook::new(Color::White, Position::new(XAxis::new(5), YAxis::new(0)))),
+            Box::new(King::new(Color::White, Position::new(XAxis::new(3), YAxis::new(0)))),
+        ];
+        let board = Board::with_rules(pieces, 6, 6, Rules::minichess());
+
+        let castling = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Castling { .. })))
+            .count();
+        assert_eq!(castling, 0);
+    }
+
+    #[test]
+    fn ten_by_eight_test() {
+        let board = Board::ten_by_eight();
+
+        assert_eq!(board.get_dimensions(), (10, 8));
+        assert_eq!(board.get_pieces().count(), 40);
+        assert!(board.is_inside(&Position::new(9.into(), 7.into())));
+
+        // The rook stands on the tenth file
+        let rook = board
+            .find_pieces(Some(PieceType::Rook), Some(Color::Black))
+            .map(|rook| rook.get_position())
+            .max_by_key(|position| position.x)
+            .unwrap();
+        assert_eq!(rook, Position::new(9.into(), 7.into()));
+    }
+
+    fn drops(board: &Board) -> Vec<Move> {
+        board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Drop(_))))
+            .collect()
+    }
+
+    #[test]
+    fn crazyhouse_capture_goes_to_pocket_test() {
+        let mut board = Board::crazyhouse();
+        let square = |x: i8, y: i8| Position::new(x.into(), y.into());
+
+        board.execute_move(Move::new(square(4, 1), square(4, 3), None));
+        board.execute_move(Move::new(square(3, 6), square(3, 4), None));
+        board.execute_move(Move::new(square(4, 3), square(3, 4), Some(Effect::Capture)));
+
+        assert_eq!(board.get_pocket(Color::White), &[PieceType::Pawn]);
+        assert!(board.get_pocket(Color::Black).is_empty());
+        assert!(drops(&board).is_empty());
+
+        board.execute_move(Move::new(square(3, 7), square(3, 4), Some(Effect::Capture)));
+        assert_eq!(board.get_pocket(Color::Black), &[PieceType::Pawn]);
+
+        // White drops the pawn it captured
+        let drop = Move::new(
+            square(2, 3),
+            square(2, 3),
+            Some(Effect::Drop(PieceType::Pawn)),
+        );
+        assert!(drops(&board).contains(&drop));
+        let record = board.execute_move(drop);
+        assert_eq!(record.piece, PieceType::Pawn);
+        assert!(board.get_pocket(Color::White).is_empty());
+        assert!(board
+            .find_pieces(Some(PieceType::Pawn), Some(Color::White))
+            .any(|pawn| pawn.get_position() == square(2, 3)));
+
+        // Now black can drop the other one
+        assert!(!drops(&board).is_empty());
+    }
+
+    #[test]
+    fn no_pawn_drops_on_first_and_last_rank_test() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1").unwrap();
+
+        let drops = drops(&board);
+        // Every empty square from the second to the seventh rank
+        assert_eq!(drops.len(), 48);
+        assert!(drops
+            .iter()
+            .all(|mov| mov.destination.y.0 != 0 && mov.destination.y.0 != 7));
+    }
+
+    #[test]
+    fn drops_must_block_check_test() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1").unwrap();
+
+        let destinations: HashSet<_> = drops(&board)
+            .into_iter()
+            .map(|mov| mov.destination.x.0)
+            .collect();
+        assert_eq!(destinations, HashSet::from([1, 2, 3]));
+    }
+
+    #[test]
+    fn captured_promoted_piece_becomes_pawn_test() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4K3/q~6R[] w - - 0 1").unwrap();
+
+        board.execute_move(Move::new(
+            Position::new(7.into(), 0.into()),
+            Position::new(0.into(), 0.into()),
+            Some(Effect::Capture),
+        ));
+
+        assert_eq!(board.get_pocket(Color::White), &[PieceType::Pawn]);
+    }
+
+    #[test]
+    fn crazyhouse_promotion_is_marked_test() {
+        let mut board = Board::from_fen("8/4P3/8/8/8/8/k7/4K3[] w - - 0 1").unwrap();
+
+        board.execute_move(Move::new(
+            Position::new(4.into(), 6.into()),
+            Position::new(4.into(), 7.into()),
+            Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Queen))),
+        ));
+
+        assert_eq!(board.to_fen(), "4Q~3/8/8/8/8/8/k7/4K3[] b - - 0 1");
+    }
+
+    fn atomic(fen: &str) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_rules(Rules::atomic());
+        board
+    }
+
+    #[test]
+    fn atomic_explosion_test() {
+        let mut board = atomic("4k3/8/8/2nbr3/3p4/4P3/8/3QK3 w - - 0 1");
+
+        let record = board.execute_move(Move::new(
+            Position::new(3.into(), 0.into()),
+            Position::new(3.into(), 3.into()),
+            Some(Effect::Capture),
+        ));
+
+        assert_eq!(record.captured, Some((PieceType::Pawn, Color::Black)));
+        // The queen, the pawn it took and the pieces around them are gone, but not the pawn on e3
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/4P3/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn atomic_king_cant_capture_test() {
+        let board = atomic("4k3/8/8/8/8/8/3n4/4K3 w - - 0 1");
+
+        let moves = board.get_legal_moves();
+        assert!(moves.iter().all(|mov| mov.effect.is_none()));
+        assert_eq!(moves.len(), 3);
+    }
+
+    #[test]
+    fn atomic_capture_next_to_own_king_test() {
+        let board = atomic("4k3/8/8/8/8/8/3p4/3QK3 w - - 0 1");
+
+        assert!(!board
+            .get_legal_moves()
+            .iter()
+            .any(|mov| mov.effect == Some(Effect::Capture)));
+    }
+
+    #[test]
+    fn atomic_kings_touching_test() {
+        let board = atomic("4r3/8/8/8/8/8/8/3kK3 w - - 0 1");
+
+        assert!(!board.is_in_check(Color::White));
+    }
+
+    #[test]
+    fn atomic_king_explodes_test() {
+        let mut board = atomic("4k3/4r3/8/8/8/8/8/4R1K1 w - - 0 1");
+
+        board.execute_move(Move::new(
+            Position::new(4.into(), 0.into()),
+            Position::new(4.into(), 6.into()),
+            Some(Effect::Capture),
+        ));
+
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::WhiteWins, Termination::Explosion))
+        );
+    }
+
+    fn antichess(fen: &str) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_rules(Rules::antichess());
+        board
+    }
+
+    #[test]
+    fn antichess_compulsory_capture_test() {
+        let mut board = Board::with_variant(Variant::Antichess);
+
+        board.execute_move(Move::new(
+            Position::new(4.into(), 1.into()),
+            Position::new(4.into(), 2.into()),
+            None,
+        ));
+        board.execute_move(Move::new(
+            Position::new(1.into(), 6.into()),
+            Position::new(1.into(), 4.into()),
+            None,
+        ));
+
+        // The bishop has to take the pawn
+        let moves = board.get_legal_moves();
+        assert_eq!(
+            moves,
+            vec![Move::new(
+                Position::new(5.into(), 0.into()),
+                Position::new(1.into(), 4.into()),
+                Some(Effect::Capture)
+            )]
+        );
+        assert_eq!(
+            board.get_moves_from(Position::new(6.into(), 0.into())),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn antichess_no_check_test() {
+        let board = antichess("4k3/4r3/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert!(!board.is_in_check(Color::White));
+        // The king can stay where the rook can take it
+        assert_eq!(board.get_legal_moves().len(), 5);
+    }
+
+    #[test]
+    fn antichess_losing_every_piece_wins_test() {
+        let mut board = antichess("8/8/8/8/8/8/1p6/K7 w - - 0 1");
+
+        board.execute_move(Move::new(
+            Position::new(0.into(), 0.into()),
+            Position::new(1.into(), 1.into()),
+            Some(Effect::Capture),
+        ));
+
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::BlackWins, Termination::AllPiecesLost))
+        );
+    }
+
+    #[test]
+    fn antichess_stalemate_wins_test() {
+        // The pawn is blocked, so white can't move
+        let board = antichess("8/8/8/8/p7/P7/8/8 w - - 0 1");
+
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::WhiteWins, Termination::Stalemate))
+        );
+    }
+
+    #[test]
+    fn antichess_promotion_to_king_test() {
+        let mut board = antichess("8/P7/8/8/8/8/8/7k w - - 0 1");
+
+        board.execute_move(Move::new(
+            Position::new(0.into(), 6.into()),
+            Position::new(0.into(), 7.into()),
+            Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::King))),
+        ));
+
+        assert_eq!(board.find_pieces(Some(PieceType::King), None).count(), 2);
+    }
+
+    #[test]
+    fn variant_names_test() {
+        for variant in Variant::ALL {
+            assert_eq!(variant.to_string().parse(), Ok(variant));
+        }
+        assert_eq!("Losing".parse(), Ok(Variant::Antichess));
+        // Losers chess keeps check and checkmate, it isn't Antichess
+        assert!("losers".parse::<Variant>().is_err());
+        assert!("bughouse".parse::<Variant>().is_err());
+        assert!("chess".parse::<Variant>().is_err());
+    }
+
+    fn with_rules_of(variant: Variant, fen: &str) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_rules(variant.rules());
+        board
+    }
+
+    fn square(x: i8, y: i8) -> Position {
+        Position::new(x.into(), y.into())
+    }
+
+    #[test]
+    fn racing_kings_start_test() {
+        let board = Board::with_variant(Variant::RacingKings);
+
+        assert_eq!(board.get_pieces().count(), 16);
+        assert_eq!(board.get_legal_moves().len(), 21);
+    }
+
+    #[test]
+    fn racing_kings_no_check_test() {
+        let board = with_rules_of(Variant::RacingKings, "8/k7/8/8/8/8/8/1R2K3 w - - 0 1");
+
+        let moves = board.get_legal_moves();
+        assert!(!moves.contains(&Move::new(square(1, 0), square(0, 0), None)));
+        assert!(!moves.contains(&Move::new(square(1, 0), square(1, 6), None)));
+        assert!(moves.contains(&Move::new(square(1, 0), square(1, 5), None)));
+    }
+
+    #[test]
+    fn racing_kings_race_test() {
+        let mut board = with_rules_of(Variant::RacingKings, "8/6K1/8/8/8/k7/8/8 w - - 0 1");
+        board.execute_move(Move::new(square(6, 6), square(6, 7), None));
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::WhiteWins, Termination::RaceFinished))
+        );
+
+        // Black can still reach the last rank, which is a draw
+        let mut board = with_rules_of(Variant::RacingKings, "8/k5K1/8/8/8/8/8/8 w - - 0 1");
+        board.execute_move(Move::new(square(6, 6), square(6, 7), None));
+        assert_eq!(board.outcome(), None);
+        board.execute_move(Move::new(square(0, 6), square(0, 7), None));
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::Draw, Termination::RaceFinished))
+        );
+    }
+
+    #[test]
+    fn horde_start_test() {
+        let board = Board::with_variant(Variant::Horde);
+
+        assert_eq!(
+            board
+                .find_pieces(Some(PieceType::Pawn), Some(Color::White))
+                .count(),
+            36
+        );
+        assert_eq!(board.find_pieces(None, Some(Color::White)).count(), 36);
+        assert_eq!(board.outcome(), None);
+    }
+
+    #[test]
+    fn horde_first_rank_double_step_test() {
+        let board = with_rules_of(Variant::Horde, "4k3/8/8/8/8/8/8/P7 w - - 0 1");
+
+        let moves = board.get_moves_from(square(0, 0)).unwrap();
+        assert!(moves.contains(&Move::new(square(0, 0), square(0, 1), None)));
+        assert!(moves.contains(&Move::new(square(0, 0), square(0, 2), None)));
+    }
+
+    #[test]
+    fn pawn_double_step_blocked_test() {
+        let board = Board::from_fen("4k3/8/8/8/8/n7/P7/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(board.get_moves_from(square(0, 1)), Some(Vec::new()));
+    }
+
+    #[test]
+    fn horde_every_piece_captured_test() {
+        let mut board = with_rules_of(Variant::Horde, "4k3/8/8/8/8/8/8/Pr6 b - - 0 1");
+
+        board.execute_move(Move::new(square(1, 0), square(0, 0), Some(Effect::Capture)));
+
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::BlackWins, Termination::AllPiecesLost))
+        );
+    }
+
+    #[test]
+    fn fog_of_war_visible_squares_test() {
+        let mut board = Board::with_variant(Variant::FogOfWar);
+
+        // The first three ranks and the squares the pawns can advance to
+        assert_eq!(board.visible_squares(Color::White).len(), 32);
+        assert_eq!(board.view_for(Color::White).get_pieces().count(), 16);
+
+        board.execute_move(Move::new(square(4, 1), square(4, 3), None));
+        board.execute_move(Move::new(square(3, 6), square(3, 4), None));
+
+        // The pawn on d5 can be captured, so white can see it
+        let view = board.view_for(Color::White);
+        assert_eq!(view.get_pieces().count(), 17);
+        assert!(view
+            .find_pieces(Some(PieceType::Pawn), Some(Color::Black))
+            .any(|pawn| pawn.get_position() == square(3, 4)));
+    }
+
+    #[test]
+    fn fog_of_war_king_capture_test() {
+        let mut board = with_rules_of(Variant::FogOfWar, "4k3/8/8/8/8/8/8/4RK2 w - - 0 1");
+
+        // There's no check, so the king can stay where it can be captured
+        assert!(!board.is_in_check(Color::Black));
+        board.execute_move(Move::new(square(4, 0), square(4, 7), Some(Effect::Capture)));
+
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::WhiteWins, Termination::KingCaptured))
+        );
+    }
+
+    #[test]
+    fn capablanca_test() {
+        let board = Board::capablanca();
+        assert_eq!(board.get_dimensions(), (10, 8));
+        assert_eq!(board.get_pieces().count(), 40);
+        assert_eq!(
+            Some(board.to_fen().as_str()),
+            Variant::Capablanca.starting_position()
+        );
+
+        // The king goes from f1 to i1 or c1, next to the corner like in standard chess
+        let board = with_rules_of(
+            Variant::Capablanca,
+            "5k4/10/10/10/10/10/10/R4K3R w KQ - 0 1",
+        );
+        let castlings: Vec<_> = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Castling { .. })))
+            .filter(|mov| mov.origin == square(5, 0))
+            .map(|mov| mov.destination)
+            .collect();
+        assert_eq!(castlings.len(), 2);
+        assert!(castlings.contains(&square(8, 0)));
+        assert!(castlings.contains(&square(2, 0)));
+    }
+
+    #[test]
+    fn archbishop_and_chancellor_test() {
+        let board = Board::from_fen("4k5/10/10/10/4A5/10/10/4K4C w - - 0 1").unwrap();
+        let moves_of = |x, y| board.get_moves_from(square(x, y)).unwrap_or_default();
+
+        let archbishop = moves_of(4, 3);
+        assert!(archbishop.iter().any(|mov| mov.destination == square(7, 6)));
+        assert!(archbishop.iter().any(|mov| mov.destination == square(5, 5)));
+        assert!(!archbishop.iter().any(|mov| mov.destination == square(4, 4)));
+
+        let chancellor = moves_of(9, 0);
+        assert!(chancellor.iter().any(|mov| mov.destination == square(9, 7)));
+        assert!(chancellor.iter().any(|mov| mov.destination == square(8, 2)));
+        assert!(!chancellor.iter().any(|mov| mov.destination == square(8, 1)));
+    }
+
+    #[test]
+    fn grand_test() {
+        let board = Board::grand();
+        assert_eq!(board.get_dimensions(), (10, 10));
+        assert!(!board.get_rules().castling);
+
+        // Pawns start on the third rank, and can still advance two squares
+        let pawn = board.get_moves_from(square(0, 2)).unwrap();
+        assert!(pawn.iter().any(|mov| mov.destination == square(0, 4)));
+
+        // Also when the position is read from FEN with the rules of Grand chess
+        let fen = Variant::Grand.starting_position().unwrap();
+        let board = Board::from_fen_with_rules(fen, Variant::Grand.rules()).unwrap();
+        let pawn = board.get_moves_from(square(0, 2)).unwrap();
+        assert!(pawn.iter().any(|mov| mov.destination == square(0, 4)));
+
+        let mut board = with_rules_of(Variant::Grand, "4k5/P9/10/10/10/10/10/10/10/4K5 w - - 0 1");
+        let promotion = Move::new(
+            square(0, 8),
+            square(0, 9),
+            Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Chancellor))),
+        );
+        board.execute_move(promotion);
+        assert!(board
+            .find_pieces(Some(PieceType::Chancellor), Some(Color::White))
+            .any(|chancellor| chancellor.get_position() == square(0, 9)));
+    }
+
+    #[test]
+    fn undo_test() {
+        let mut board = Board::default();
+        assert_eq!(board.undo(), None);
+
+        let fens: Vec<_> = [
+            "e2e4", "d7d5", "e4d5", "d8d5", "g1f3", "c8g4", "f1e2", "b8c6",
+        ]
+        .into_iter()
+        .map(|text| {
+            let fen = board.to_fen();
+            board.execute_move(board.parse_move(text).unwrap());
+            fen
+        })
+        .collect();
+        // Castling changes the rights as well
+        let castled = board.clone();
+        board.execute_move(board.parse_move("e1g1").unwrap());
+        assert_eq!(board.get_history().len(), 9);
+
+        let record = board.undo().unwrap();
+        assert!(record.castling.is_some());
+        assert_eq!(board.to_fen(), castled.to_fen());
+        for fen in fens.iter().rev() {
+            board.undo().unwrap();
+            assert_eq!(&board.to_fen(), fen);
+        }
+        assert_eq!(board.undo(), None);
+        // The clone kept its own history
+        assert_eq!(castled.get_history().len(), 8);
+        assert_eq!(
+            castled.get_history()[2].captured,
+            Some((PieceType::Pawn, Color::Black))
+        );
+    }
+
+    #[test]
+    fn undo_promotion_test() {
+        let mut board = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let fen = board.to_fen();
+        board.execute_move(board.parse_move("a7b8q").unwrap());
+        board.undo();
+        assert_eq!(board.to_fen(), fen);
+        assert!(board
+            .find_pieces(Some(PieceType::Pawn), Some(Color::White))
+            .any(|pawn| pawn.get_position() == square(0, 6)));
+    }
 }
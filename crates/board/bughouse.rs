@@ -0,0 +1,293 @@
+use crate::{
+    Board, Clock, Color, Game, GameError, GameResult, Move, MoveRecord, Termination, Variant,
+};
+
+/// One of the two boards of a Bughouse match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BughouseBoard {
+    A,
+    B,
+}
+
+impl BughouseBoard {
+    /// The board where the partners of the players of this one play
+    pub fn other(self) -> Self {
+        match self {
+            BughouseBoard::A => BughouseBoard::B,
+            BughouseBoard::B => BughouseBoard::A,
+        }
+    }
+}
+
+/// The first team plays white on board A and black on board B, the second one plays black on
+/// board A and white on board B
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Team {
+    First,
+    Second,
+}
+
+impl Team {
+    /// Team of the player of the given color on the given board
+    pub fn of(board: BughouseBoard, color: Color) -> Self {
+        match (board, color) {
+            (BughouseBoard::A, Color::White) | (BughouseBoard::B, Color::Black) => Team::First,
+            // Only white and black play Bughouse
+            _ => Team::Second,
+        }
+    }
+}
+
+/// Result of the whole match. It ends as soon as one of the boards has a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult {
+    Ongoing,
+    Win {
+        team: Team,
+        board: BughouseBoard,
+        termination: Termination,
+    },
+    Draw {
+        board: BughouseBoard,
+        termination: Termination,
+    },
+}
+
+/// A Bughouse match: two games played at the same time, where the pieces captured on one board
+/// are given to the partner of the capturer, who can drop them on the other board. Checkmate is
+/// decided with the pieces the player has in their pocket at that moment
+pub struct Bughouse {
+    a: Game,
+    b: Game,
+    result: MatchResult,
+}
+
+impl Bughouse {
+    /// The players of board A come first. The white player of each board is the partner of the
+    /// black player of the other one
+    pub fn new(
+        a_white: impl Into<String>,
+        a_black: impl Into<String>,
+        b_white: impl Into<String>,
+        b_black: impl Into<String>,
+    ) -> Self {
+        Bughouse {
+            a: Game::with_board(Board::with_variant(Variant::Bughouse), a_white, a_black),
+            b: Game::with_board(Board::with_variant(Variant::Bughouse), b_white, b_black),
+            result: MatchResult::Ongoing,
+        }
+    }
+
+    pub fn get_game(&self, board: BughouseBoard) -> &Game {
+        match board {
+            BughouseBoard::A => &self.a,
+            BughouseBoard::B => &self.b,
+        }
+    }
+
+    fn game_mut(&mut self, board: BughouseBoard) -> &mut Game {
+        match board {
+            BughouseBoard::A => &mut self.a,
+            BughouseBoard::B => &mut self.b,
+        }
+    }
+
+    pub fn get_result(&self) -> MatchResult {
+        self.result
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result != MatchResult::Ongoing
+    }
+
+    /// Plays the board on a clock. Each board has its own clock, with one side for each of its
+    /// players
+    pub fn set_clock(&mut self, board: BughouseBoard, clock: Clock) {
+        self.game_mut(board).set_clock(clock);
+    }
+
+    /// Plays a move on the given board, for the player whose turn it is there. A capture gives
+    /// the piece to the partner of the capturer
+    pub fn play(&mut self, board: BughouseBoard, mov: Move) -> Result<MoveRecord, GameError> {
+        self.check_flags();
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+
+        let record = self.game_mut(board).play(mov)?;
+        if record.captured.is_some() {
+            // The capture went to the pocket of the capturer, promoted pieces already turned
+            // back into pawns
+            let capturer = record.color;
+            let pocket = self.get_game(board).get_board().get_pocket(capturer);
+            if let Some(piece) = pocket.last().copied() {
+                self.game_mut(board)
+                    .get_board_mut()
+                    .remove_from_pocket(capturer, piece);
+                // The partner plays the other color on the other board
+                self.game_mut(board.other())
+                    .get_board_mut()
+                    .add_to_pocket(!capturer, piece);
+            }
+        }
+
+        self.update_result(board);
+        Ok(record)
+    }
+
+    /// Ends the match if a player ran out of time on any board. Returns the board and the color
+    /// of the flagged player
+    pub fn check_flags(&mut self) -> Option<(BughouseBoard, Color)> {
+        if self.is_over() {
+            return None;
+        }
+        for board in [BughouseBoard::A, BughouseBoard::B] {
+            if let Some(flagged) = self.game_mut(board).check_flag() {
+                self.update_result(board);
+                return Some((board, flagged));
+            }
+        }
+        None
+    }
+
+    pub fn resign(&mut self, board: BughouseBoard, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        self.game_mut(board).resign(color)?;
+        self.update_result(board);
+        Ok(())
+    }
+
+    /// The first board to finish decides the match, and the clock of the other one stops
+    fn update_result(&mut self, board: BughouseBoard) {
+        let game = self.get_game(board);
+        let Some(termination) = game.get_termination() else {
+            return;
+        };
+        self.result = match game.get_result() {
+            GameResult::WhiteWins => MatchResult::Win {
+                team: Team::of(board, Color::White),
+                board,
+                termination,
+            },
+            GameResult::BlackWins => MatchResult::Win {
+                team: Team::of(board, Color::Black),
+                board,
+                termination,
+            },
+            GameResult::Draw => MatchResult::Draw { board, termination },
+            GameResult::Ongoing => return,
+        };
+        self.game_mut(board.other()).stop_clock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Effect, ManualTimeSource, PieceType, Position, TimeControl};
+
+    use std::time::Duration;
+
+    fn mov(origin: (i8, i8), destination: (i8, i8), effect: Option<Effect>) -> Move {
+        Move::new(
+            Position::new(origin.0.into(), origin.1.into()),
+            Position::new(destination.0.into(), destination.1.into()),
+            effect,
+        )
+    }
+
+    fn bughouse() -> Bughouse {
+        Bughouse::new("Alice", "Bob", "Carol", "Dave")
+    }
+
+    #[test]
+    fn capture_goes_to_partner_test() {
+        let mut bughouse = bughouse();
+        let a = BughouseBoard::A;
+
+        bughouse.play(a, mov((4, 1), (4, 3), None)).unwrap();
+        bughouse.play(a, mov((3, 6), (3, 4), None)).unwrap();
+        bughouse
+            .play(a, mov((4, 3), (3, 4), Some(Effect::Capture)))
+            .unwrap();
+
+        // White captured on board A, so black on board B gets the pawn
+        let board_a = bughouse.get_game(a).get_board();
+        assert!(board_a.get_pocket(Color::White).is_empty());
+        let board_b = bughouse.get_game(BughouseBoard::B).get_board();
+        assert_eq!(board_b.get_pocket(Color::Black), &[PieceType::Pawn]);
+
+        // And can drop it there once it's their turn
+        let b = BughouseBoard::B;
+        bughouse.play(b, mov((4, 1), (4, 3), None)).unwrap();
+        let drop = mov((3, 3), (3, 3), Some(Effect::Drop(PieceType::Pawn)));
+        bughouse.play(b, drop).unwrap();
+        let board_b = bughouse.get_game(b).get_board();
+        assert!(board_b.get_pocket(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn checkmate_ends_match_test() {
+        let mut bughouse = bughouse();
+        let b = BughouseBoard::B;
+
+        // Fool's mate, black wins on board B
+        bughouse.play(b, mov((5, 1), (5, 2), None)).unwrap();
+        bughouse.play(b, mov((4, 6), (4, 4), None)).unwrap();
+        bughouse.play(b, mov((6, 1), (6, 3), None)).unwrap();
+        bughouse.play(b, mov((3, 7), (7, 3), None)).unwrap();
+
+        assert_eq!(
+            bughouse.get_result(),
+            MatchResult::Win {
+                team: Team::First,
+                board: b,
+                termination: Termination::Checkmate
+            }
+        );
+        assert_eq!(
+            bughouse.play(BughouseBoard::A, mov((4, 1), (4, 3), None)),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn timeout_test() {
+        let mut bughouse = bughouse();
+        let control = TimeControl::sudden_death(Duration::from_secs(60));
+        let time = ManualTimeSource::new();
+        for board in [BughouseBoard::A, BughouseBoard::B] {
+            let clock = Clock::with_time_source(control.clone(), time.clone());
+            bughouse.set_clock(board, clock);
+        }
+
+        bughouse
+            .play(BughouseBoard::A, mov((4, 1), (4, 3), None))
+            .unwrap();
+        time.advance(Duration::from_secs(61));
+
+        // White on board B never moved, black on board A ran out of time as well
+        assert_eq!(
+            bughouse.check_flags(),
+            Some((BughouseBoard::A, Color::Black))
+        );
+        assert_eq!(
+            bughouse.get_result(),
+            MatchResult::Win {
+                team: Team::First,
+                board: BughouseBoard::A,
+                termination: Termination::Timeout
+            }
+        );
+        assert_eq!(
+            bughouse
+                .get_game(BughouseBoard::B)
+                .get_clock()
+                .unwrap()
+                .get_running(),
+            None
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use crate::Color;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where the clock reads the time from
+pub trait TimeSource: Send {
+    /// Time elapsed since an arbitrary, fixed, point in time
+    fn now(&self) -> Duration;
+}
+
+/// Reads the monotonic clock of the system
+pub struct SystemTimeSource {
+    start: Instant,
+}
+
+impl SystemTimeSource {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time only passes when it's told to. Clones share the same time, so one copy can be given to
+/// the clock and the other one kept to move time forward
+#[derive(Clone, Default)]
+pub struct ManualTimeSource {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualTimeSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Time given to a player on each move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Increment {
+    None,
+    /// Added after every move
+    Fischer(Duration),
+    /// The time used on a move is given back after it, up to the delay
+    Bronstein(Duration),
+    /// The clock only starts running once the delay has passed. Also known as US delay
+    Delay(Duration),
+}
+
+/// A period of the game with its own time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stage {
+    /// Moves to be played in the stage. None means until the end of the game
+    pub moves: Option<u32>,
+    /// Time added to the clock when the stage starts
+    pub time: Duration,
+    pub increment: Increment,
+}
+
+/// The stages of a game. Once the last stage is over, it is played again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    pub fn new(stages: Vec<Stage>) -> Self {
+        assert!(
+            !stages.is_empty(),
+            "A time control needs at least one stage"
+        );
+        Self { stages }
+    }
+
+    pub fn sudden_death(time: Duration) -> Self {
+        Self::with_increment(time, Increment::None)
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        Self::with_increment(time, Increment::Fischer(increment))
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        Self::with_increment(time, Increment::Bronstein(delay))
+    }
+
+    pub fn simple_delay(time: Duration, delay: Duration) -> Self {
+        Self::with_increment(time, Increment::Delay(delay))
+    }
+
+    fn with_increment(time: Duration, increment: Increment) -> Self {
+        Self::new(vec![Stage {
+            moves: None,
+            time,
+            increment,
+        }])
+    }
+
+    pub fn get_stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    fn stage(&self, index: usize) -> Stage {
+        self.stages[index.min(self.stages.len() - 1)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimeControlError(String);
+
+impl fmt::Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid time control: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseTimeControlError {}
+
+/// Stages are separated by ':' and written as `[moves/]minutes[+seconds|dseconds|bseconds]`,
+/// where '+' is a Fischer increment, 'd' a simple delay and 'b' a Bronstein delay.
+/// For example "40/90+30:30+30" or "5d3"
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseTimeControlError(s.to_string());
+
+        let stages = s
+            .split(':')
+            .map(|stage| {
+                let (moves, rest) = match stage.split_once('/') {
+                    Some((moves, rest)) => {
+                        let moves = moves.trim().parse().map_err(|_| error())?;
+                        (Some(moves), rest)
+                    }
+                    None => (None, stage),
+                };
+
+                let split = rest.find(['+', 'd', 'b']);
+                let (minutes, increment) = match split {
+                    Some(index) => (
+                        &rest[..index],
+                        Some((&rest[index..=index], &rest[index + 1..])),
+                    ),
+                    None => (rest, None),
+                };
+                let minutes: f64 = minutes.trim().parse().map_err(|_| error())?;
+                let time = Duration::try_from_secs_f64(minutes * 60.0).map_err(|_| error())?;
+
+                let increment = match increment {
+                    Some((kind, seconds)) => {
+                        let seconds: f64 = seconds.trim().parse().map_err(|_| error())?;
+                        let seconds = Duration::try_from_secs_f64(seconds).map_err(|_| error())?;
+                        match kind {
+                            "+" => Increment::Fischer(seconds),
+                            "d" => Increment::Delay(seconds),
+                            _ => Increment::Bronstein(seconds),
+                        }
+                    }
+                    None => Increment::None,
+                };
+
+                Ok(Stage {
+                    moves,
+                    time,
+                    increment,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TimeControl::new(stages))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Side {
+    remaining: Duration,
+    /// Moves played in the current stage
+    moves: u32,
+    stage: usize,
+}
+
+/// A chess clock. Only one side runs at a time, pressing the clock after a move starts the
+/// opponent's time
+pub struct Clock {
+    control: TimeControl,
+    source: Box<dyn TimeSource>,
+    sides: BTreeMap<Color, Side>,
+    /// The side whose time is running
+    running: Option<Color>,
+    /// When the running side started its move
+    started: Duration,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_time_source(control, SystemTimeSource::new())
+    }
+
+    pub fn with_time_source(control: TimeControl, source: impl TimeSource + 'static) -> Self {
+        let side = Side {
+            remaining: control.stage(0).time,
+            moves: 0,
+            stage: 0,
+        };
+        let sides = [Color::White, Color::Black]
+            .into_iter()
+            .map(|color| (color, side))
+            .collect();
+
+        Self {
+            control,
+            source: Box::new(source),
+            sides,
+            running: None,
+            started: Duration::ZERO,
+        }
+    }
+
+    pub fn get_time_control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    pub fn get_running(&self) -> Option<Color> {
+        self.running
+    }
+
+    /// Starts the time of the given side
+    pub fn start(&mut self, color: Color) {
+        self.running = Some(color);
+        self.started = self.source.now();
+    }
+
+    /// Stops the clock without ending the move of the running side
+    pub fn stop(&mut self) {
+        if let Some(color) = self.running {
+            let remaining = self.get_remaining(color);
+            self.side_mut(color).remaining = remaining;
+        }
+        self.running = None;
+    }
+
+    /// The given side finished its move: applies the increment and starts the opponent's time.
+    /// Returns the flagged side if the time ran out before the clock was pressed. Presses from
+    /// a side whose time isn't running are ignored
+    pub fn press(&mut self, color: Color) -> Option<Color> {
+        if let Some(flagged) = self.get_flagged() {
+            self.running = None;
+            return Some(flagged);
+        }
+        if self.running != Some(color) {
+            return None;
+        }
+
+        let elapsed = self.source.now() - self.started;
+        let remaining = self.get_remaining(color);
+        let control = self.control.clone();
+        let side = self.side_mut(color);
+        let stage = control.stage(side.stage);
+
+        side.remaining = remaining
+            + match stage.increment {
+                Increment::None | Increment::Delay(_) => Duration::ZERO,
+                Increment::Fischer(increment) => increment,
+                Increment::Bronstein(delay) => elapsed.min(delay),
+            };
+
+        side.moves += 1;
+        if stage.moves == Some(side.moves) {
+            side.stage += 1;
+            side.moves = 0;
+            side.remaining += control.stage(side.stage).time;
+        }
+
+        self.start(!color);
+        None
+    }
+
+    /// Time left for the given side, including the time used on the current move
+    pub fn get_remaining(&self, color: Color) -> Duration {
+        let side = self.sides[&color];
+        if self.running != Some(color) {
+            return side.remaining;
+        }
+
+        let elapsed = self.source.now() - self.started;
+        let used = match self.control.stage(side.stage).increment {
+            Increment::Delay(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        };
+        side.remaining.saturating_sub(used)
+    }
+
+    /// The running side, if it ran out of time
+    pub fn get_flagged(&self) -> Option<Color> {
+        self.running
+            .filter(|color| self.get_remaining(*color).is_zero())
+    }
+
+    fn side_mut(&mut self, color: Color) -> &mut Side {
+        self.sides.get_mut(&color).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn sudden_death_flag_test() {
+        let time = ManualTimeSource::new();
+        let mut clock =
+            Clock::with_time_source(TimeControl::sudden_death(seconds(60)), time.clone());
+        clock.start(Color::White);
+
+        time.advance(seconds(20));
+        assert_eq!(clock.get_remaining(Color::White), seconds(40));
+        assert_eq!(clock.press(Color::White), None);
+
+        time.advance(seconds(10));
+        assert_eq!(clock.get_remaining(Color::White), seconds(40));
+        assert_eq!(clock.get_remaining(Color::Black), seconds(50));
+        assert_eq!(clock.press(Color::Black), None);
+
+        time.advance(seconds(41));
+        assert_eq!(clock.get_remaining(Color::White), Duration::ZERO);
+        assert_eq!(clock.get_flagged(), Some(Color::White));
+        assert_eq!(clock.press(Color::White), Some(Color::White));
+    }
+
+    #[test]
+    fn fischer_increment_test() {
+        let time = ManualTimeSource::new();
+        let control = TimeControl::fischer(seconds(60), seconds(5));
+        let mut clock = Clock::with_time_source(control, time.clone());
+        clock.start(Color::White);
+
+        time.advance(seconds(2));
+        clock.press(Color::White);
+
+        assert_eq!(clock.get_remaining(Color::White), seconds(63));
+        assert_eq!(clock.get_running(), Some(Color::Black));
+    }
+
+    #[test]
+    fn press_twice_test() {
+        let time = ManualTimeSource::new();
+        let control = TimeControl::fischer(seconds(60), seconds(5));
+        let mut clock = Clock::with_time_source(control, time.clone());
+        clock.start(Color::White);
+
+        time.advance(seconds(2));
+        clock.press(Color::White);
+        time.advance(seconds(4));
+        // White's time isn't running, so there's no increment and black keeps losing time
+        assert_eq!(clock.press(Color::White), None);
+        assert_eq!(clock.get_remaining(Color::White), seconds(63));
+        assert_eq!(clock.get_running(), Some(Color::Black));
+        time.advance(seconds(1));
+        assert_eq!(clock.get_remaining(Color::Black), seconds(55));
+    }
+
+    #[test]
+    fn bronstein_delay_test() {
+        let time = ManualTimeSource::new();
+        let control = TimeControl::bronstein(seconds(60), seconds(5));
+        let mut clock = Clock::with_time_source(control, time.clone());
+        clock.start(Color::White);
+
+        // The time is running during the delay, but it's given back
+        time.advance(seconds(3));
+        assert_eq!(clock.get_remaining(Color::White), seconds(57));
+        clock.press(Color::White);
+        assert_eq!(clock.get_remaining(Color::White), seconds(60));
+
+        // Only up to the delay is given back
+        time.advance(seconds(8));
+        clock.press(Color::Black);
+        assert_eq!(clock.get_remaining(Color::Black), seconds(57));
+    }
+
+    #[test]
+    fn simple_delay_test() {
+        let time = ManualTimeSource::new();
+        let control = TimeControl::simple_delay(seconds(60), seconds(5));
+        let mut clock = Clock::with_time_source(control, time.clone());
+        clock.start(Color::White);
+
+        time.advance(seconds(3));
+        assert_eq!(clock.get_remaining(Color::White), seconds(60));
+        time.advance(seconds(4));
+        assert_eq!(clock.get_remaining(Color::White), seconds(58));
+        clock.press(Color::White);
+        assert_eq!(clock.get_remaining(Color::White), seconds(58));
+    }
+
+    #[test]
+    fn multiple_stages_test() {
+        let time = ManualTimeSource::new();
+        let control: TimeControl = "2/1+10:1".parse().unwrap();
+        let mut clock = Clock::with_time_source(control, time.clone());
+        clock.start(Color::White);
+
+        for _ in 0..2 {
+            time.advance(seconds(10));
+            clock.press(Color::White);
+            clock.press(Color::Black);
+        }
+
+        // 60 - 10 + 10 - 10 + 10 and then the time of the second stage
+        assert_eq!(clock.get_remaining(Color::White), seconds(120));
+
+        // The second stage has no increment
+        time.advance(seconds(10));
+        clock.press(Color::White);
+        assert_eq!(clock.get_remaining(Color::White), seconds(110));
+    }
+
+    #[test]
+    fn parse_time_control_test() {
+        let control: TimeControl = "40/90+30:30+30".parse().unwrap();
+        assert_eq!(
+            control.get_stages(),
+            &[
+                Stage {
+                    moves: Some(40),
+                    time: seconds(90 * 60),
+                    increment: Increment::Fischer(seconds(30)),
+                },
+                Stage {
+                    moves: None,
+                    time: seconds(30 * 60),
+                    increment: Increment::Fischer(seconds(30)),
+                },
+            ]
+        );
+
+        let control: TimeControl = "5d3".parse().unwrap();
+        assert_eq!(
+            control,
+            TimeControl::simple_delay(seconds(5 * 60), seconds(3))
+        );
+
+        let control: TimeControl = "3b2".parse().unwrap();
+        assert_eq!(control, TimeControl::bronstein(seconds(3 * 60), seconds(2)));
+
+        assert!("40/".parse::<TimeControl>().is_err());
+        assert!("five".parse::<TimeControl>().is_err());
+    }
+}
@@ -0,0 +1,466 @@
+use crate::{Betza, BetzaError, Board, ChoiceOfPromotablePiece, FenError, Rules, Variant};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A variant described in a text file, in INI format, so that it can be tried without
+/// recompiling. For example:
+///
+/// ```ini
+/// ; Lines starting with ; or # are comments
+/// [variant]
+/// name = Chancellor chess
+/// width = 9
+/// height = 9
+/// start = rnbqkcnbr/ppppppppp/9/9/9/9/9/PPPPPPPPP/RNBQKCNBR w KQkq - 0 1
+/// castling = true
+/// double_step = true
+/// drops = false
+/// ; Rank where pawns are promoted, counted from the side of each player
+/// promotion_rank = 9
+/// promotions = q c r b n
+/// ; checkmate, or the name of a variant whose win condition is used, like threecheck
+/// win = checkmate
+///
+/// [pieces]
+/// ; Fairy pieces, by their letter in the starting position, and their Betza notation
+/// c = RN
+/// ```
+///
+/// Only the dimensions and the starting position are required
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantDefinition {
+    pub name: String,
+    pub width: u8,
+    pub height: u8,
+    /// Starting position in FEN
+    pub start: String,
+    pub rules: Rules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionError {
+    /// A line that isn't a section, a key = value pair or a comment
+    Syntax {
+        line: usize,
+        text: String,
+    },
+    UnknownSection {
+        line: usize,
+        section: String,
+    },
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    /// A value that can't be read for its key
+    Value {
+        line: usize,
+        key: String,
+        value: String,
+    },
+    /// One of the required keys isn't there
+    Missing(&'static str),
+    Betza {
+        letter: char,
+        error: BetzaError,
+    },
+    Fen(FenError),
+    /// The starting position doesn't have the size of the board
+    Dimensions {
+        width: u8,
+        height: u8,
+    },
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Syntax { line, text } => {
+                write!(f, "line {line}: invalid line: {text}")
+            }
+            DefinitionError::UnknownSection { line, section } => {
+                write!(f, "line {line}: unknown section: {section}")
+            }
+            DefinitionError::UnknownKey { line, key } => {
+                write!(f, "line {line}: unknown key: {key}")
+            }
+            DefinitionError::Value { line, key, value } => {
+                write!(f, "line {line}: invalid value for {key}: {value}")
+            }
+            DefinitionError::Missing(key) => write!(f, "missing key: {key}"),
+            DefinitionError::Betza { letter, error } => write!(f, "piece {letter}: {error}"),
+            DefinitionError::Fen(error) => write!(f, "invalid starting position: {error}"),
+            DefinitionError::Dimensions { width, height } => {
+                write!(f, "the starting position isn't {width}x{height}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+impl From<FenError> for DefinitionError {
+    fn from(error: FenError) -> Self {
+        DefinitionError::Fen(error)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Variant,
+    Pieces,
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// The piece a pawn can be promoted to. As in the starting position, the fairy pieces of the
+/// rules take the place of the standard pieces with the same letter
+fn parse_promotion(letter: char, rules: &Rules) -> Option<ChoiceOfPromotablePiece> {
+    let letter = letter.to_ascii_lowercase();
+    if rules.fairy_pieces.contains_key(&letter) {
+        return Some(ChoiceOfPromotablePiece::Fairy(letter));
+    }
+    let choice = match letter {
+        'q' => ChoiceOfPromotablePiece::Queen,
+        'r' => ChoiceOfPromotablePiece::Rook,
+        'b' => ChoiceOfPromotablePiece::Bishop,
+        'n' => ChoiceOfPromotablePiece::Knight,
+        'k' => ChoiceOfPromotablePiece::King,
+        'a' => ChoiceOfPromotablePiece::Archbishop,
+        'c' => ChoiceOfPromotablePiece::Chancellor,
+        _ => return None,
+    };
+    Some(choice)
+}
+
+impl FromStr for VariantDefinition {
+    type Err = DefinitionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut name = String::new();
+        let mut width = None;
+        let mut height = None;
+        let mut start = None;
+        let mut rules = Rules::default();
+        // Promotions are read once every fairy piece is known
+        let mut promotions = None;
+
+        let mut section = None;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| DefinitionError::Syntax {
+                        line: line_number,
+                        text: line.to_string(),
+                    })?;
+                section = match header.trim().to_ascii_lowercase().as_str() {
+                    "variant" => Some(Section::Variant),
+                    "pieces" => Some(Section::Pieces),
+                    _ => {
+                        return Err(DefinitionError::UnknownSection {
+                            line: line_number,
+                            section: header.to_string(),
+                        })
+                    }
+                };
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(DefinitionError::Syntax {
+                    line: line_number,
+                    text: line.to_string(),
+                });
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || DefinitionError::Value {
+                line: line_number,
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+
+            match section {
+                Some(Section::Variant) => match key {
+                    "name" => name = value.to_string(),
+                    "width" => width = Some(value.parse::<u8>().map_err(|_| invalid())?),
+                    "height" => height = Some(value.parse::<u8>().map_err(|_| invalid())?),
+                    "start" => start = Some(value.to_string()),
+                    "castling" => rules.castling = parse_bool(value).ok_or_else(invalid)?,
+                    "double_step" => {
+                        rules.pawn_double_step = parse_bool(value).ok_or_else(invalid)?
+                    }
+                    "drops" => rules.drops = parse_bool(value).ok_or_else(invalid)?,
+                    "promotion_rank" => {
+                        let rank = value.parse::<u8>().map_err(|_| invalid())?;
+                        rules.promotion_rank = Some(rank).filter(|rank| *rank > 0);
+                        if rules.promotion_rank.is_none() {
+                            return Err(invalid());
+                        }
+                    }
+                    "promotions" => {
+                        let letters = value
+                            .split([' ', ','])
+                            .filter(|letter| !letter.is_empty())
+                            .map(|letter| {
+                                let mut chars = letter.chars();
+                                match (chars.next(), chars.next()) {
+                                    (Some(letter), None) => Some(letter),
+                                    _ => None,
+                                }
+                            })
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(invalid)?;
+                        promotions = Some((letters, invalid()));
+                    }
+                    "win" => {
+                        rules.variant = match value.to_ascii_lowercase().as_str() {
+                            "checkmate" => Variant::Standard,
+                            _ => value.parse().map_err(|_| invalid())?,
+                        };
+                    }
+                    _ => {
+                        return Err(DefinitionError::UnknownKey {
+                            line: line_number,
+                            key: key.to_string(),
+                        })
+                    }
+                },
+                Some(Section::Pieces) => {
+                    let mut letters = key.chars();
+                    let (Some(letter), None) = (letters.next(), letters.next()) else {
+                        return Err(invalid());
+                    };
+                    let letter = letter.to_ascii_lowercase();
+                    // Kings and pawns have rules of their own, which a fairy piece would lose
+                    if !letter.is_ascii_alphabetic() || letter == 'k' || letter == 'p' {
+                        return Err(invalid());
+                    }
+                    let betza = value
+                        .parse::<Betza>()
+                        .map_err(|error| DefinitionError::Betza { letter, error })?;
+                    rules.fairy_pieces.insert(letter, betza);
+                }
+                None => {
+                    return Err(DefinitionError::Syntax {
+                        line: line_number,
+                        text: line.to_string(),
+                    })
+                }
+            }
+        }
+
+        if let Some((letters, invalid)) = promotions {
+            rules.promotions = letters
+                .into_iter()
+                .map(|letter| parse_promotion(letter, &rules))
+                .collect::<Option<_>>()
+                .ok_or(invalid)?;
+        }
+
+        Ok(VariantDefinition {
+            name,
+            width: width.ok_or(DefinitionError::Missing("width"))?,
+            height: height.ok_or(DefinitionError::Missing("height"))?,
+            start: start.ok_or(DefinitionError::Missing("start"))?,
+            rules,
+        })
+    }
+}
+
+impl VariantDefinition {
+    /// The starting position, with the rules of the variant
+    pub fn board(&self) -> Result<Board, DefinitionError> {
+        let board = Board::from_fen_with_rules(&self.start, self.rules.clone())?;
+        if board.get_dimensions() != (self.width, self.height) {
+            return Err(DefinitionError::Dimensions {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(board)
+    }
+}
+
+impl Board {
+    /// Reads a variant definition and returns its starting position
+    pub fn from_definition(text: &str) -> Result<Board, DefinitionError> {
+        text.parse::<VariantDefinition>()?.board()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Effect, GameResult, Move, PieceType, Position, Termination};
+
+    const DEFINITION: &str = "
+        ; A small variant with a fairy piece
+        [variant]
+        name = Wazir chess
+        width = 6
+        height = 6
+        start = rnwknr/pppppp/6/6/PPPPPP/RNWKNR w - - 0 1
+        castling = false
+        double_step = false
+        promotion_rank = 5
+        promotions = q, w
+
+        [pieces]
+        w = WF2
+    ";
+
+    fn square(x: i8, y: i8) -> Position {
+        Position::new(x.into(), y.into())
+    }
+
+    #[test]
+    fn parse_test() {
+        let definition: VariantDefinition = DEFINITION.parse().unwrap();
+        assert_eq!(definition.name, "Wazir chess");
+        assert_eq!((definition.width, definition.height), (6, 6));
+        assert!(!definition.rules.castling);
+        assert_eq!(definition.rules.promotion_rank, Some(5));
+        assert_eq!(
+            definition.rules.promotions,
+            vec![
+                ChoiceOfPromotablePiece::Queen,
+                ChoiceOfPromotablePiece::Fairy('w')
+            ]
+        );
+        assert_eq!(definition.rules.fairy_pieces.len(), 1);
+    }
+
+    #[test]
+    fn board_test() {
+        let board = Board::from_definition(DEFINITION).unwrap();
+        assert_eq!(board.get_dimensions(), (6, 6));
+        assert_eq!(
+            board
+                .find_pieces(Some(PieceType::Fairy('w')), Some(Color::White))
+                .count(),
+            1
+        );
+        assert_eq!(board.to_fen(), "rnwknr/pppppp/6/6/PPPPPP/RNWKNR w - - 0 1");
+
+        // No double steps
+        let pawn = board.get_moves_from(square(0, 1)).unwrap();
+        assert_eq!(pawn.len(), 1);
+    }
+
+    #[test]
+    fn promotion_rank_test() {
+        let definition = DEFINITION.replace("rnwknr/pppppp/6/6/PPPPPP/RNWKNR", "3k2/6/P5/6/6/3K2");
+        let mut board = Board::from_definition(&definition).unwrap();
+
+        let moves = board.get_moves_from(square(0, 3)).unwrap();
+        assert_eq!(moves[0].effect, Some(Effect::Promotion(None)));
+
+        board.execute_move(Move::new(
+            square(0, 3),
+            square(0, 4),
+            Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Fairy('w')))),
+        ));
+        assert!(board
+            .find_pieces(Some(PieceType::Fairy('w')), Some(Color::White))
+            .any(|piece| piece.get_position() == square(0, 4)));
+    }
+
+    #[test]
+    fn win_condition_test() {
+        let definition = DEFINITION
+            .replace("rnwknr/pppppp/6/6/PPPPPP/RNWKNR", "6/3k2/6/6/6/3K2")
+            .replace("win = checkmate", "")
+            .replace("[pieces]", "win = kingofthehill\n[pieces]");
+        let board = Board::from_definition(&definition).unwrap();
+        assert_eq!(board.get_rules().variant, Variant::KingOfTheHill);
+
+        let definition = definition.replace("6/3k2/6/6/6/3K2 w", "6/6/6/2K3/6/k5 b");
+        let board = Board::from_definition(&definition).unwrap();
+        assert_eq!(
+            board.outcome(),
+            Some((GameResult::WhiteWins, Termination::KingOfTheHill))
+        );
+    }
+
+    #[test]
+    fn errors_test() {
+        let error = |text: &str| Board::from_definition(text).err().unwrap();
+
+        assert_eq!(
+            error("[variant]\nwidth = 8\nheight = 8"),
+            DefinitionError::Missing("start")
+        );
+        assert_eq!(
+            error("[board]"),
+            DefinitionError::UnknownSection {
+                line: 1,
+                section: "board".to_string()
+            }
+        );
+        assert_eq!(
+            error("[variant]\ncastling = maybe"),
+            DefinitionError::Value {
+                line: 2,
+                key: "castling".to_string(),
+                value: "maybe".to_string()
+            }
+        );
+        assert_eq!(
+            error("[pieces]\nx = pQ"),
+            DefinitionError::Betza {
+                letter: 'x',
+                error: BetzaError::UnknownLetter('p')
+            }
+        );
+        assert_eq!(
+            error(&DEFINITION.replace("width = 6", "width = 7")),
+            DefinitionError::Dimensions {
+                width: 7,
+                height: 6
+            }
+        );
+        // Promotions to fairy pieces that aren't defined
+        assert!(matches!(
+            error(&DEFINITION.replace("w = WF2", "")),
+            DefinitionError::Value { .. }
+        ));
+        // Kings and pawns can't be redefined
+        for letter in ["k", "P"] {
+            assert_eq!(
+                error(&DEFINITION.replace("w = WF2", &format!("w = WF2\n{letter} = WF"))),
+                DefinitionError::Value {
+                    line: 15,
+                    key: letter.to_string(),
+                    value: "WF".to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn fairy_letter_wins_test() {
+        // The letter of the archbishop is taken by a fairy piece, in the position and promotions
+        let definition = DEFINITION
+            .replace("w = WF2", "w = WF2\na = WF")
+            .replace("promotions = q, w", "promotions = a");
+        let definition: VariantDefinition = definition.parse().unwrap();
+        assert_eq!(
+            definition.rules.promotions,
+            vec![ChoiceOfPromotablePiece::Fairy('a')]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    ChoiceOfPromotablePiece, Color, Effect, GameResult, MoveRecord, PieceType, Position,
+    Termination,
+};
+
+/// Something that happened during a game. Every move emits a PieceMoved for the piece that was
+/// moved, or a PieceDropped for drops, followed by events for its side effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    PieceMoved {
+        piece: PieceType,
+        color: Color,
+        origin: Position,
+        destination: Position,
+    },
+    PieceDropped {
+        piece: PieceType,
+        color: Color,
+        position: Position,
+    },
+    PieceCaptured {
+        piece: PieceType,
+        color: Color,
+        position: Position,
+    },
+    Castled {
+        color: Color,
+        /// Origin and destination of the king
+        king: (Position, Position),
+        /// Origin and destination of the rook
+        rook: (Position, Position),
+    },
+    Promoted {
+        color: Color,
+        position: Position,
+        piece: ChoiceOfPromotablePiece,
+    },
+    /// The king of the given color is in check
+    Check { color: Color },
+    GameOver {
+        result: GameResult,
+        termination: Termination,
+    },
+}
+
+impl GameEvent {
+    /// Events caused by a move, in the order they are emitted
+    pub fn from_record(record: &MoveRecord) -> Vec<GameEvent> {
+        let mov = record.mov;
+        let mut events = match mov.effect {
+            Some(Effect::Drop(piece)) => vec![GameEvent::PieceDropped {
+                piece,
+                color: record.color,
+                position: mov.destination,
+            }],
+            _ => vec![GameEvent::PieceMoved {
+                piece: record.piece,
+                color: record.color,
+                origin: mov.origin,
+                destination: mov.destination,
+            }],
+        };
+
+        if let Some((piece, color)) = record.captured {
+            // Pawns captured en passant stand next to the destination
+            let position = match mov.effect {
+                Some(Effect::EnPassant(victim)) => victim,
+                _ => mov.destination,
+            };
+            events.push(GameEvent::PieceCaptured {
+                piece,
+                color,
+                position,
+            });
+        }
+
+        // Atomic captures blow up the capturing piece and its neighbours too
+        for (piece, color, position) in record.exploded.iter().copied() {
+            events.push(GameEvent::PieceCaptured {
+                piece,
+                color,
+                position,
+            });
+        }
+
+        if let Some(other) = record.castling {
+            // Castling can be started by either the king or the rook
+            let moved = (mov.origin, mov.destination);
+            let (king, rook) = if record.piece == PieceType::King {
+                (moved, other)
+            } else {
+                (other, moved)
+            };
+            events.push(GameEvent::Castled {
+                color: record.color,
+                king,
+                rook,
+            });
+        }
+
+        if let Some(piece) = record.promotion {
+            events.push(GameEvent::Promoted {
+                color: record.color,
+                position: mov.destination,
+                piece,
+            });
+        }
+
+        if record.check {
+            events.push(GameEvent::Check {
+                color: !record.color,
+            });
+        }
+
+        events
+    }
+}
+
+/// Receives the events of a game. Closures taking a &GameEvent are observers as well
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+impl<F: FnMut(&GameEvent)> GameObserver for F {
+    fn on_event(&mut self, event: &GameEvent) {
+        self(event)
+    }
+}
+
+/// Identifies an observer, used to unsubscribe it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(pub(crate) usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Move, Rules};
+
+    #[test]
+    fn castling_from_rook_events_test() {
+        let rook = (
+            Position::new(7.into(), 0.into()),
+            Position::new(5.into(), 0.into()),
+        );
+        let king = (
+            Position::new(4.into(), 0.into()),
+            Position::new(6.into(), 0.into()),
+        );
+        let record = MoveRecord {
+            mov: Move::new(
+                rook.0,
+                rook.1,
+                Some(Effect::Castling {
+                    origin: king.0,
+                    destination: king.1,
+                }),
+            ),
+            piece: PieceType::Rook,
+            color: Color::White,
+            captured: None,
+            exploded: Vec::new(),
+            promotion: None,
+            castling: Some(king),
+            check: false,
+            checkmate: false,
+        };
+
+        let events = GameEvent::from_record(&record);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1],
+            GameEvent::Castled {
+                color: Color::White,
+                king,
+                rook,
+            }
+        );
+    }
+
+    #[test]
+    fn explosion_events_test() {
+        let fen = "4k3/8/8/2nb4/3p4/8/8/3QK3 w - - 0 1";
+        let mut board = Board::from_fen_with_rules(fen, Rules::atomic()).unwrap();
+        let record = board.execute_move(board.parse_move("d1d4").unwrap());
+        assert_eq!(record.captured, Some((PieceType::Pawn, Color::Black)));
+
+        let square = |x: i8, y: i8| Position::new(x.into(), y.into());
+        let mut exploded = record.exploded.clone();
+        exploded.sort_by_key(|(_, _, position)| (position.x.0, position.y.0));
+        assert_eq!(
+            exploded,
+            vec![
+                (PieceType::Knight, Color::Black, square(2, 4)),
+                (PieceType::Queen, Color::White, square(3, 3)),
+                (PieceType::Bishop, Color::Black, square(3, 4)),
+            ]
+        );
+
+        // The captured pawn, then every piece the explosion removed
+        let events = GameEvent::from_record(&record);
+        let captured = events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::PieceCaptured { .. }))
+            .count();
+        assert_eq!(captured, 4);
+        for (piece, color, position) in record.exploded {
+            assert!(events.contains(&GameEvent::PieceCaptured {
+                piece,
+                color,
+                position,
+            }));
+        }
+    }
+}
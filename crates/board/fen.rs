@@ -0,0 +1,740 @@
+use crate::board::{relative_rank, MAX_SIZE};
+use crate::notation::parse_square;
+use crate::pieces::{FairyPiece, Promoted};
+use crate::{Board, Color, PieceType, Position, Rules, Variant, XAxis, YAxis};
+
+use std::collections::HashSet;
+use std::fmt;
+
+pub const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// One of the six fields is missing
+    MissingField(&'static str),
+    Placement(String),
+    Turn(String),
+    Castling(String),
+    EnPassant(String),
+    Counter(String),
+    /// The checks given in Three-check, like +2+0
+    Checks(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {field} field"),
+            FenError::Placement(field) => write!(f, "invalid piece placement: {field}"),
+            FenError::Turn(field) => write!(f, "invalid side to move: {field}"),
+            FenError::Castling(field) => write!(f, "invalid castling availability: {field}"),
+            FenError::EnPassant(field) => write!(f, "invalid en passant square: {field}"),
+            FenError::Counter(field) => write!(f, "invalid move counter: {field}"),
+            FenError::Checks(field) => write!(f, "invalid check counters: {field}"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+pub(crate) fn piece_to_char(piece: PieceType, color: Color) -> char {
+    let letter = match piece {
+        PieceType::Bishop => 'b',
+        PieceType::King => 'k',
+        PieceType::Knight => 'n',
+        PieceType::Pawn => 'p',
+        PieceType::Queen => 'q',
+        PieceType::Rook => 'r',
+        PieceType::Archbishop => 'a',
+        PieceType::Chancellor => 'c',
+        PieceType::Fairy(letter) => letter,
+    };
+    write_color(letter, color)
+}
+
+fn write_color(letter: char, color: Color) -> char {
+    match color {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+        _ => unreachable!("FEN can't be written for four-player boards"),
+    }
+}
+
+pub(crate) fn piece_from_char(letter: char) -> Option<(PieceType, Color)> {
+    let piece = match letter.to_ascii_lowercase() {
+        'b' => PieceType::Bishop,
+        'k' => PieceType::King,
+        'n' => PieceType::Knight,
+        'p' => PieceType::Pawn,
+        'q' => PieceType::Queen,
+        'r' => PieceType::Rook,
+        'a' => PieceType::Archbishop,
+        'c' => PieceType::Chancellor,
+        _ => return None,
+    };
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some((piece, color))
+}
+
+/// The fairy piece of the rules written with the letter, if there's one
+fn fairy_from_char(letter: char, rules: &Rules) -> Option<(PieceType, Color)> {
+    let piece = letter.to_ascii_lowercase();
+    if !rules.fairy_pieces.contains_key(&piece) {
+        return None;
+    }
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some((PieceType::Fairy(piece), color))
+}
+
+fn file_to_char(x: XAxis) -> char {
+    (b'a' + x.0 as u8) as char
+}
+
+/// Rank where the pieces of the given color start
+fn back_rank(board: &Board, color: Color) -> YAxis {
+    let (bl, _, ul, _) = board.get_limits();
+    match color {
+        Color::Black => ul.0.y,
+        _ => bl.0.y,
+    }
+}
+
+impl Board {
+    /// Reads a position in Forsyth-Edwards Notation. The castling field can be written as in
+    /// standard FEN (KQkq), X-FEN or Shredder-FEN (files of the rooks, like HAha). The size of the
+    /// board is taken from the piece placement. Crazyhouse positions write the pockets after the
+    /// placement, like [Qp], and mark promoted pieces with a ~. Three-check positions have a
+    /// seventh field with the checks given by each player, like +2+0
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        Board::from_fen_with_rules(fen, Rules::default())
+    }
+
+    /// Like from_fen, but the board gets the given rules. The letters of their fairy pieces can
+    /// be used in the placement. Pockets switch to Crazyhouse rules only if the rules don't have
+    /// drops already
+    pub fn from_fen_with_rules(fen: &str, rules: Rules) -> Result<Board, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::MissingField("placement"))?;
+        let turn = fields.next().ok_or(FenError::MissingField("turn"))?;
+        let castling = fields.next().ok_or(FenError::MissingField("castling"))?;
+        let en_passant = fields.next().ok_or(FenError::MissingField("en passant"))?;
+        let halfmove = fields
+            .next()
+            .ok_or(FenError::MissingField("halfmove clock"))?;
+        let fullmove = fields
+            .next()
+            .ok_or(FenError::MissingField("fullmove number"))?;
+
+        let placement_error = || FenError::Placement(placement.to_string());
+        let (placement, pockets) = match placement.split_once('[') {
+            Some((placement, pockets)) => {
+                let pockets = pockets.strip_suffix(']').ok_or_else(placement_error)?;
+                (placement, Some(pockets))
+            }
+            None => (placement, None),
+        };
+        let ranks: Vec<_> = placement.split('/').collect();
+        let height = ranks.len();
+        let mut width = None;
+        let mut pieces = Vec::new();
+        for (row, rank) in ranks.iter().enumerate() {
+            let y = YAxis::new((height - 1 - row) as i8);
+            let mut x = 0;
+            let mut empty = 0;
+            for letter in rank.chars() {
+                if letter == '~' {
+                    let piece = pieces.pop().ok_or_else(placement_error)?;
+                    pieces.push(Box::new(Promoted::new(piece)));
+                    continue;
+                }
+                if let Some(digit) = letter.to_digit(10) {
+                    // Wide boards can have more than 9 empty squares in a row
+                    empty = empty * 10 + digit as usize;
+                    continue;
+                }
+                x += empty;
+                empty = 0;
+                let (piece, color) = fairy_from_char(letter, &rules)
+                    .or_else(|| piece_from_char(letter))
+                    .ok_or_else(placement_error)?;
+                let position = Position::new(XAxis::new(x as i8), y);
+                pieces.push(match piece {
+                    PieceType::Fairy(letter) => {
+                        let betza = rules.fairy_pieces[&letter].clone();
+                        Box::new(FairyPiece::new(letter, betza, color, position))
+                    }
+                    _ => Board::new_piece(piece, color, position).ok_or_else(placement_error)?,
+                });
+                x += 1;
+            }
+            x += empty;
+            if *width.get_or_insert(x) != x {
+                return Err(placement_error());
+            }
+        }
+        let width = width.ok_or_else(placement_error)?;
+        if width == 0 || width > MAX_SIZE as usize || height > MAX_SIZE as usize {
+            return Err(placement_error());
+        }
+
+        let rules = match pockets {
+            Some(_) if !rules.drops => Rules::crazyhouse(),
+            _ => rules,
+        };
+        let mut board = Board::with_rules(pieces, width as u8, height as u8, rules);
+        for letter in pockets.unwrap_or_default().chars() {
+            let (piece, color) = fairy_from_char(letter, board.get_rules())
+                .or_else(|| piece_from_char(letter))
+                .ok_or_else(placement_error)?;
+            board.add_to_pocket(color, piece);
+        }
+
+        let turn = match turn {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::Turn(turn.to_string())),
+        };
+
+        let castling_rooks = board
+            .parse_castling(castling)
+            .ok_or_else(|| FenError::Castling(castling.to_string()))?;
+        board.set_castling_rights(&castling_rooks);
+
+        let halfmove = halfmove
+            .parse()
+            .map_err(|_| FenError::Counter(halfmove.to_string()))?;
+        let fullmove = fullmove
+            .parse()
+            .map_err(|_| FenError::Counter(fullmove.to_string()))?;
+        board.set_state(turn, halfmove, fullmove);
+
+        // The square has to be one an enemy pawn just skipped
+        if en_passant != "-" {
+            let en_passant_error = || FenError::EnPassant(en_passant.to_string());
+            let square = match parse_square(en_passant) {
+                Some((square, "")) if board.is_inside(&square) => square,
+                _ => return Err(en_passant_error()),
+            };
+            board.set_en_passant(Some(square));
+            let empty = board
+                .get_pieces()
+                .all(|piece| piece.get_position() != square);
+            if !empty || board.en_passant_victim(turn).is_none() {
+                return Err(en_passant_error());
+            }
+        }
+
+        // Three-check positions end with the checks given by each player
+        if let Some(checks) = fields.next() {
+            let checks_error = || FenError::Checks(checks.to_string());
+            let counters: Vec<_> = checks
+                .strip_prefix('+')
+                .ok_or_else(checks_error)?
+                .split('+')
+                .map(|counter| counter.parse().map_err(|_| checks_error()))
+                .collect::<Result<_, _>>()?;
+            let [white, black] = counters[..] else {
+                return Err(checks_error());
+            };
+            if board.get_rules().variant != Variant::ThreeCheck {
+                board.set_rules(Variant::ThreeCheck.rules());
+            }
+            board.set_checks(Color::White, white);
+            board.set_checks(Color::Black, black);
+        }
+
+        Ok(board)
+    }
+
+    /// Positions of the rooks that can castle
+    fn parse_castling(&self, castling: &str) -> Option<HashSet<Position>> {
+        let mut rooks = HashSet::new();
+        if castling == "-" {
+            return Some(rooks);
+        }
+
+        for letter in castling.chars() {
+            let color = if letter.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let y = back_rank(self, color);
+            let king = self
+                .find_pieces(Some(PieceType::King), Some(color))
+                .map(|king| king.get_position())
+                .find(|king| king.y == y)?;
+            let mut candidates = self
+                .find_pieces(Some(PieceType::Rook), Some(color))
+                .map(|rook| rook.get_position())
+                .filter(|rook| rook.y == y);
+
+            let rook = match letter.to_ascii_lowercase() {
+                // The outermost rook of each side
+                'k' => candidates
+                    .filter(|rook| rook.x > king.x)
+                    .max_by_key(|rook| rook.x)?,
+                'q' => candidates
+                    .filter(|rook| rook.x < king.x)
+                    .min_by_key(|rook| rook.x)?,
+                file @ 'a'..='z' => {
+                    let x = XAxis::new((file as u8 - b'a') as i8);
+                    candidates.find(|rook| rook.x == x)?
+                }
+                _ => return None,
+            };
+            rooks.insert(rook);
+        }
+
+        Some(rooks)
+    }
+
+    /// Only the given rooks, and their kings, are left as not moved
+    fn set_castling_rights(&mut self, rooks: &HashSet<Position>) {
+        let (_, _, _, ur) = self.get_limits();
+        // Grand chess pawns start on the third rank
+        let last_starting_rank = match self.get_rules().variant {
+            Variant::Grand => 2,
+            _ => 1,
+        };
+        let colors_with_rights: HashSet<_> = self
+            .find_pieces(Some(PieceType::Rook), None)
+            .filter(|rook| rooks.contains(&rook.get_position()))
+            .map(|rook| rook.get_color())
+            .collect();
+
+        for piece in self.get_pieces_mut() {
+            let color = piece.get_color();
+            let position = piece.get_position();
+            let moved = match piece.get_type() {
+                PieceType::Rook => !rooks.contains(&position),
+                PieceType::King => !colors_with_rights.contains(&color),
+                // Pawns can only advance two squares from the second rank, or from the first one
+                // in Horde
+                PieceType::Pawn => relative_rank(color, position, ur.0) > last_starting_rank,
+                _ => false,
+            };
+            piece.set_moved(moved);
+        }
+    }
+
+    /// Writes the position in Forsyth-Edwards Notation. Castling is written as KQkq when the
+    /// rook is the outermost one, and with the file of the rook otherwise (X-FEN). Panics on
+    /// four-player boards, since FEN only knows about white and black
+    pub fn to_fen(&self) -> String {
+        self.fen(false)
+    }
+
+    /// Same as to_fen, but castling is always written with the files of the rooks
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen(true)
+    }
+
+    fn fen(&self, shredder: bool) -> String {
+        assert!(
+            self.get_rules().variant != Variant::FourPlayer,
+            "FEN can't be written for four-player boards"
+        );
+        let (width, height) = self.get_dimensions();
+
+        let ranks: Vec<String> = (0..height as i8)
+            .rev()
+            .map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+                for x in 0..width as i8 {
+                    let position = Position::new(XAxis::new(x), YAxis::new(y));
+                    match self
+                        .get_pieces()
+                        .find(|piece| piece.get_position() == position)
+                    {
+                        Some(piece) => {
+                            if empty > 0 {
+                                rank.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            rank.push(piece_to_char(piece.get_type(), piece.get_color()));
+                            if piece.was_promoted() && self.get_rules().drops {
+                                rank.push('~');
+                            }
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                }
+                rank
+            })
+            .collect();
+
+        let mut placement = ranks.join("/");
+        if self.get_rules().drops {
+            let pocket: String = [Color::White, Color::Black]
+                .into_iter()
+                .flat_map(|color| {
+                    self.get_pocket(color)
+                        .iter()
+                        .map(move |piece| piece_to_char(*piece, color))
+                })
+                .collect();
+            placement.push_str(&format!("[{pocket}]"));
+        }
+
+        let turn = match self.get_turn() {
+            Color::Black => "b",
+            _ => "w",
+        };
+
+        let mut castling = String::new();
+        for color in [Color::White, Color::Black] {
+            castling.extend(self.castling_letters(color, shredder));
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant_capture()
+            .map_or("-".to_string(), |square| square.to_string());
+
+        let mut fen = format!(
+            "{} {} {} {} {} {}",
+            placement,
+            turn,
+            castling,
+            en_passant,
+            self.get_halfmove_clock(),
+            self.get_fullmove_number()
+        );
+        if self.get_rules().variant == Variant::ThreeCheck {
+            let white = self.get_checks(Color::White);
+            let black = self.get_checks(Color::Black);
+            fen.push_str(&format!(" +{white}+{black}"));
+        }
+        fen
+    }
+
+    fn castling_letters(&self, color: Color, shredder: bool) -> Vec<char> {
+        let y = back_rank(self, color);
+        let Some(king) = self
+            .find_pieces(Some(PieceType::King), Some(color))
+            .find(|king| king.get_position().y == y && !king.was_moved())
+            .map(|king| king.get_position())
+        else {
+            return Vec::new();
+        };
+
+        let rooks: Vec<_> = self
+            .find_pieces(Some(PieceType::Rook), Some(color))
+            .map(|rook| (rook.get_position(), rook.was_moved()))
+            .filter(|(rook, _)| rook.y == y)
+            .collect();
+        let outermost_kingside = rooks.iter().map(|(rook, _)| rook.x).max();
+        let outermost_queenside = rooks.iter().map(|(rook, _)| rook.x).min();
+
+        let mut castling: Vec<_> = rooks
+            .iter()
+            .filter(|(_, moved)| !moved)
+            .map(|(rook, _)| rook.x)
+            .collect();
+        // Kingside first
+        castling.sort_by(|a, b| b.cmp(a));
+
+        castling
+            .into_iter()
+            .map(|x| {
+                let letter = if shredder {
+                    file_to_char(x)
+                } else if x > king.x && Some(x) == outermost_kingside {
+                    'k'
+                } else if x < king.x && Some(x) == outermost_queenside {
+                    'q'
+                } else {
+                    file_to_char(x)
+                };
+                write_color(letter, color)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Effect, Move};
+
+    fn castle(board: &mut Board, king: (i8, i8), rook: (i8, i8)) {
+        let king = Position::new(king.0.into(), king.1.into());
+        let rook = Position::new(rook.0.into(), rook.1.into());
+        let mov = board
+            .get_moves_from(king)
+            .unwrap()
+            .into_iter()
+            .find(
+                |mov| matches!(mov.effect, Some(Effect::Castling { origin, .. }) if origin == rook),
+            )
+            .unwrap();
+        board.execute_move(mov);
+    }
+
+    fn square(board: &Board, x: i8, y: i8) -> Option<(PieceType, Color)> {
+        let position = Position::new(x.into(), y.into());
+        board
+            .get_pieces()
+            .find(|piece| piece.get_position() == position)
+            .map(|piece| (piece.get_type(), piece.get_color()))
+    }
+
+    #[test]
+    fn starting_position_test() {
+        assert_eq!(Board::default().to_fen(), STARTING_POSITION);
+
+        let board = Board::from_fen(STARTING_POSITION).unwrap();
+        assert_eq!(board.to_fen(), STARTING_POSITION);
+        assert_eq!(board.get_legal_moves().len(), 20);
+        assert_eq!(
+            board.to_shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rk2r2r/8/8/8/8/8/8/RK2R2R w Eq - 0 1",
+        ];
+        for fen in fens {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn counters_test() {
+        let mut board = Board::default();
+        board.execute_move(Move::new(
+            Position::new(6.into(), 0.into()),
+            Position::new(5.into(), 2.into()),
+            None,
+        ));
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1"
+        );
+
+        board.execute_move(Move::new(
+            Position::new(4.into(), 6.into()),
+            Position::new(4.into(), 4.into()),
+            None,
+        ));
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 2"
+        );
+    }
+
+    #[test]
+    fn castling_rights_test() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+
+        let castlings: Vec<_> = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Castling { .. })))
+            .collect();
+        // The king and the rook can both start the castling
+        assert_eq!(castlings.len(), 2);
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        castle(&mut board, (4, 0), (0, 0));
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+    }
+
+    #[test]
+    fn castling_through_check_test() {
+        // The rook on f8 covers the square the king goes through
+        let board = Board::from_fen("5r1k/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+        let castlings = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Castling { .. })))
+            .count();
+        assert_eq!(castlings, 0);
+    }
+
+    #[test]
+    fn crazyhouse_pockets_test() {
+        let fen = "r1bqk2r/pppp1ppp/2n5/4p3/2B1n3/8/PPPP1PPP/RNB1K1NR[QPbp] w KQkq - 0 6";
+        let board = Board::from_fen(fen).unwrap();
+
+        assert!(board.get_rules().drops);
+        assert_eq!(
+            board.get_pocket(Color::White),
+            &[PieceType::Queen, PieceType::Pawn]
+        );
+        assert_eq!(
+            board.get_pocket(Color::Black),
+            &[PieceType::Bishop, PieceType::Pawn]
+        );
+        assert_eq!(board.to_fen(), fen);
+
+        assert_eq!(
+            Board::crazyhouse().to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn three_check_counters_test() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3 +2+0";
+        let board = Board::from_fen(fen).unwrap();
+
+        assert_eq!(board.get_rules().variant, Variant::ThreeCheck);
+        assert_eq!(board.get_checks(Color::White), 2);
+        assert_eq!(board.get_checks(Color::Black), 0);
+        assert_eq!(board.to_fen(), fen);
+
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 1 +2"),
+            Err(FenError::Checks(_))
+        ));
+    }
+
+    #[test]
+    fn wide_board_test() {
+        let fen = "rnnbqkbnnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNNBQKBNNR w KQkq - 0 1";
+        assert_eq!(Board::ten_by_eight().to_fen(), fen);
+
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.get_dimensions(), (10, 8));
+
+        // Files are named a to z, and ranks go as far
+        let too_tall = vec!["8"; 27].join("/");
+        assert!(matches!(
+            Board::from_fen(&format!("{too_tall} w - - 0 1")),
+            Err(FenError::Placement(_))
+        ));
+        assert!(Board::from_fen("26/26 w - - 0 1").is_ok());
+        assert!(Board::from_fen("27/27 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn en_passant_test() {
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        assert!(board.parse_move("e5d6").is_ok());
+        assert!(board.parse_move("e5f6").is_err());
+
+        // Only written when a pawn can capture on it
+        let mut board = Board::default();
+        board.execute_move(board.parse_move("e2e4").unwrap());
+        assert_eq!(
+            board.get_en_passant(),
+            Some(Position::new(4.into(), 2.into()))
+        );
+        assert!(board.to_fen().contains(" b KQkq - 0 1"));
+
+        // There has to be a pawn past the square, which is empty
+        for square in ["d5", "e6", "e3", "i6", "d66"] {
+            let fen = format!("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq {square} 0 3");
+            assert!(
+                matches!(Board::from_fen(&fen), Err(FenError::EnPassant(_))),
+                "{square}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_fen_test() {
+        assert!(matches!(
+            Board::from_fen("8/8/8 w - - 0"),
+            Err(FenError::MissingField("fullmove number"))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/9/8/8/8/8/8/8 w - - 0 1"),
+            Err(FenError::Placement(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/8 x - - 0 1"),
+            Err(FenError::Turn(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+            Err(FenError::Castling(_))
+        ));
+    }
+
+    #[test]
+    fn chess960_positions_test() {
+        let standard = Board::chess960(518).to_fen();
+        assert_eq!(standard, STARTING_POSITION);
+
+        let first = Board::chess960(0).to_fen();
+        assert_eq!(
+            first,
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+
+        // Every position is different and has the king between the rooks
+        let mut back_ranks = HashSet::new();
+        for index in 0..960 {
+            let board = Board::chess960(index);
+            let fen = board.to_fen();
+            let back_rank = fen.split('/').next_back().unwrap()[..8].to_string();
+            let king = back_rank.find('K').unwrap();
+            let rooks: Vec<_> = back_rank.match_indices('R').map(|(x, _)| x).collect();
+            assert!(rooks[0] < king && king < rooks[1]);
+            back_ranks.insert(back_rank);
+        }
+        assert_eq!(back_ranks.len(), 960);
+    }
+
+    #[test]
+    fn chess960_castling_test() {
+        // The king and the rook swap squares
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/5KR1 w G - 0 1").unwrap();
+        castle(&mut board, (5, 0), (6, 0));
+        assert_eq!(square(&board, 6, 0), Some((PieceType::King, Color::White)));
+        assert_eq!(square(&board, 5, 0), Some((PieceType::Rook, Color::White)));
+
+        // The king moves towards the rook, the rook jumps over it
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/RK6 w A - 0 1").unwrap();
+        castle(&mut board, (1, 0), (0, 0));
+        assert_eq!(square(&board, 2, 0), Some((PieceType::King, Color::White)));
+        assert_eq!(square(&board, 3, 0), Some((PieceType::Rook, Color::White)));
+        assert_eq!(square(&board, 0, 0), None);
+        assert_eq!(square(&board, 1, 0), None);
+
+        // The king doesn't move at all
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+        castle(&mut board, (6, 0), (7, 0));
+        assert_eq!(square(&board, 6, 0), Some((PieceType::King, Color::White)));
+        assert_eq!(square(&board, 5, 0), Some((PieceType::Rook, Color::White)));
+    }
+
+    #[test]
+    fn shredder_castling_test() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let board = Board::from_fen(fen).unwrap();
+
+        assert_eq!(board.to_shredder_fen(), fen);
+        assert_eq!(
+            board.to_fen(),
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use crate::game::same_move;
+use crate::{Board, Color, Effect, GameError, Move, MoveRecord, PieceType, Termination, Variant};
+
+use std::collections::BTreeMap;
+
+/// Points for checkmating a player, capturing their king, or being stalemated
+const ELIMINATION_POINTS: u32 = 20;
+
+/// Points for capturing a piece. Promoted pieces are only worth a pawn
+fn capture_points(piece: PieceType, promoted: bool) -> u32 {
+    if promoted {
+        return 1;
+    }
+    match piece {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 3,
+        PieceType::Bishop | PieceType::Rook => 5,
+        PieceType::Archbishop => 7,
+        PieceType::Chancellor => 8,
+        PieceType::Queen => 9,
+        PieceType::King | PieceType::Fairy(_) => 0,
+    }
+}
+
+/// A game of four-player chess. Players are taken out once they are checkmated, stalemated,
+/// lose their king or resign, and the game ends when only one is left. Captures and
+/// eliminations give points, and the player with the most points wins
+pub struct FourPlayerGame {
+    board: Board,
+    scores: BTreeMap<Color, u32>,
+    /// Players that are out, in the order they left the game
+    eliminated: Vec<(Color, Termination)>,
+}
+
+impl Default for FourPlayerGame {
+    fn default() -> Self {
+        FourPlayerGame::new()
+    }
+}
+
+impl FourPlayerGame {
+    pub fn new() -> Self {
+        FourPlayerGame::with_board(Board::four_player())
+    }
+
+    /// Plays from the given position, which should have the rules of four-player chess
+    pub fn with_board(board: Board) -> Self {
+        debug_assert!(board.get_rules().variant == Variant::FourPlayer);
+        FourPlayerGame {
+            board,
+            scores: Color::FOUR_PLAYERS.map(|color| (color, 0)).into(),
+            eliminated: Vec::new(),
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_score(&self, color: Color) -> u32 {
+        self.scores.get(&color).copied().unwrap_or_default()
+    }
+
+    pub fn get_eliminated(&self) -> &[(Color, Termination)] {
+        &self.eliminated
+    }
+
+    /// Players still in the game, in turn order
+    pub fn get_players(&self) -> Vec<Color> {
+        Color::FOUR_PLAYERS
+            .into_iter()
+            .filter(|color| !self.is_eliminated(*color))
+            .collect()
+    }
+
+    pub fn is_eliminated(&self, color: Color) -> bool {
+        self.eliminated.iter().any(|(player, _)| *player == color)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.get_players().len() <= 1
+    }
+
+    /// Every player with their score, from the highest to the lowest. Ties keep the turn order
+    pub fn standings(&self) -> Vec<(Color, u32)> {
+        let mut standings: Vec<_> = Color::FOUR_PLAYERS
+            .into_iter()
+            .map(|color| (color, self.get_score(color)))
+            .collect();
+        standings.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        standings
+    }
+
+    /// The player with the most points, once the game is over
+    pub fn get_winner(&self) -> Option<Color> {
+        if !self.is_over() {
+            return None;
+        }
+        self.standings().first().map(|(color, _)| *color)
+    }
+
+    pub fn play(&mut self, mov: Move) -> Result<MoveRecord, GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        match mov.effect {
+            Some(Effect::Promotion(None)) => return Err(GameError::PromotionNotChosen),
+            Some(Effect::Promotion(Some(choice)))
+                if !self.board.get_rules().promotions.contains(&choice) =>
+            {
+                return Err(GameError::IllegalMove);
+            }
+            _ => (),
+        }
+        let is_legal = self
+            .board
+            .get_legal_moves()
+            .into_iter()
+            .any(|legal| same_move(legal, mov));
+        if !is_legal {
+            return Err(GameError::IllegalMove);
+        }
+
+        // Whether the piece was promoted is lost once it's captured
+        let promoted = self
+            .board
+            .get_pieces()
+            .find(|piece| piece.get_position() == mov.destination)
+            .is_some_and(|piece| piece.was_promoted());
+        let record = self.board.execute_move(mov);
+
+        if let Some((piece, color)) = record.captured {
+            if piece == PieceType::King {
+                self.add_points(record.color, ELIMINATION_POINTS);
+                self.eliminate(color, Termination::KingCaptured);
+            } else {
+                self.add_points(record.color, capture_points(piece, promoted));
+            }
+        }
+        self.eliminate_stuck_players(record.color);
+        Ok(record)
+    }
+
+    /// The player leaves the game and their pieces are removed from the board
+    pub fn resign(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() || self.is_eliminated(color) {
+            return Err(GameError::GameOver);
+        }
+        self.eliminate(color, Termination::Resignation);
+        self.eliminate_stuck_players(color);
+        Ok(())
+    }
+
+    /// Takes out the players without legal moves, for as long as the one whose turn it is has
+    /// none. The given player made the last move and gets the points for a checkmate
+    fn eliminate_stuck_players(&mut self, last_mover: Color) {
+        while !self.is_over() && self.board.get_legal_moves().is_empty() {
+            let stuck = self.board.get_turn();
+            if self.board.is_in_check(stuck) {
+                self.add_points(last_mover, ELIMINATION_POINTS);
+                self.eliminate(stuck, Termination::Checkmate);
+            } else {
+                self.add_points(stuck, ELIMINATION_POINTS);
+                self.eliminate(stuck, Termination::Stalemate);
+            }
+        }
+    }
+
+    fn eliminate(&mut self, color: Color, termination: Termination) {
+        self.eliminated.push((color, termination));
+        self.board.eliminate(color);
+    }
+
+    fn add_points(&mut self, color: Color, points: u32) {
+        *self.scores.entry(color).or_default() += points;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Piece, Position};
+
+    fn square(x: i8, y: i8) -> Position {
+        Position::new(x.into(), y.into())
+    }
+
+    fn mov(origin: (i8, i8), destination: (i8, i8), effect: Option<Effect>) -> Move {
+        Move::new(
+            square(origin.0, origin.1),
+            square(destination.0, destination.1),
+            effect,
+        )
+    }
+
+    /// Only the kings, in their starting squares, plus the given pieces
+    fn game_with(pieces: Vec<Box<dyn Piece>>, turn: Color) -> FourPlayerGame {
+        let kings = [
+            (Color::Red, (7, 0)),
+            (Color::Blue, (0, 6)),
+            (Color::Yellow, (6, 13)),
+            (Color::Green, (13, 7)),
+        ];
+        let mut pieces = pieces;
+        for (color, (x, y)) in kings {
+            pieces.extend(Board::new_piece(PieceType::King, color, square(x, y)));
+        }
+        let mut board = Board::with_rules(pieces, 14, 14, Variant::FourPlayer.rules());
+        board.set_state(turn, 0, 1);
+        FourPlayerGame::with_board(board)
+    }
+
+    #[test]
+    fn start_test() {
+        let game = FourPlayerGame::new();
+        let board = game.get_board();
+        assert_eq!(board.get_dimensions(), (14, 14));
+        assert_eq!(board.get_turn(), Color::Red);
+        assert_eq!(board.get_pieces().count(), 64);
+        assert!(!board.is_inside(&square(2, 2)));
+        assert!(!board.is_inside(&square(11, 13)));
+        assert!(board.is_inside(&square(3, 0)));
+        // Each pawn can advance one or two squares, and both knights have two moves
+        assert_eq!(board.get_legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn turn_order_test() {
+        let mut game = FourPlayerGame::new();
+        game.play(mov((7, 1), (7, 3), None)).unwrap();
+        assert_eq!(game.get_board().get_turn(), Color::Blue);
+        // Blue pawns advance to the right
+        game.play(mov((1, 7), (3, 7), None)).unwrap();
+        assert_eq!(game.get_board().get_turn(), Color::Yellow);
+        game.play(mov((7, 12), (7, 10), None)).unwrap();
+        // Green pawns advance to the left
+        assert_eq!(
+            game.play(mov((12, 7), (13, 7), None)),
+            Err(GameError::IllegalMove)
+        );
+        game.play(mov((12, 7), (10, 7), None)).unwrap();
+        assert_eq!(game.get_board().get_turn(), Color::Red);
+        assert_eq!(game.get_board().get_fullmove_number(), 2);
+    }
+
+    #[test]
+    fn promotion_in_the_middle_test() {
+        let pawn = Board::new_piece(PieceType::Pawn, Color::Blue, square(6, 4)).unwrap();
+        let game = game_with(vec![pawn], Color::Blue);
+        let promotions = game
+            .get_board()
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| matches!(mov.effect, Some(Effect::Promotion(_))))
+            .count();
+        assert_eq!(promotions, 1);
+    }
+
+    #[test]
+    fn king_capture_test() {
+        // Red left yellow in check, and blue takes the king before yellow can move
+        let pieces = vec![
+            Board::new_piece(PieceType::Rook, Color::Red, square(6, 5)).unwrap(),
+            Board::new_piece(PieceType::Rook, Color::Blue, square(3, 13)).unwrap(),
+            Board::new_piece(PieceType::Queen, Color::Yellow, square(10, 13)).unwrap(),
+        ];
+        let mut game = game_with(pieces, Color::Blue);
+        let capture = Some(Effect::Capture);
+        game.play(mov((3, 13), (6, 13), capture)).unwrap();
+
+        assert_eq!(game.get_score(Color::Blue), 20);
+        assert_eq!(
+            game.get_eliminated(),
+            &[(Color::Yellow, Termination::KingCaptured)]
+        );
+        // The rest of yellow's pieces leave with the king
+        let board = game.get_board();
+        assert_eq!(board.find_pieces(None, Some(Color::Yellow)).count(), 0);
+        assert_eq!(board.get_turn(), Color::Green);
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn checkmate_and_captures_test() {
+        let pieces = vec![
+            Board::new_piece(PieceType::Rook, Color::Red, square(5, 3)).unwrap(),
+            Board::new_piece(PieceType::Rook, Color::Red, square(1, 10)).unwrap(),
+            Board::new_piece(PieceType::Knight, Color::Yellow, square(5, 9)).unwrap(),
+        ];
+        let mut game = game_with(pieces, Color::Red);
+        game.play(mov((5, 3), (0, 3), None)).unwrap();
+
+        assert_eq!(game.get_score(Color::Red), 20);
+        assert_eq!(
+            game.get_eliminated(),
+            &[(Color::Blue, Termination::Checkmate)]
+        );
+        assert_eq!(game.get_board().get_turn(), Color::Yellow);
+
+        // Yellow moves the knight away, then green and red play on
+        game.play(mov((5, 9), (4, 11), None)).unwrap();
+        game.play(mov((13, 7), (12, 7), None)).unwrap();
+        game.play(mov((1, 10), (4, 10), None)).unwrap();
+        game.play(mov((6, 13), (5, 13), None)).unwrap();
+        game.play(mov((12, 7), (13, 7), None)).unwrap();
+        game.play(mov((4, 10), (4, 11), Some(Effect::Capture)))
+            .unwrap();
+        assert_eq!(game.get_score(Color::Red), 23);
+        assert_eq!(game.standings()[0], (Color::Red, 23));
+
+        game.resign(Color::Yellow).unwrap();
+        game.resign(Color::Green).unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.get_winner(), Some(Color::Red));
+    }
+}
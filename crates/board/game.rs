@@ -0,0 +1,664 @@
+use crate::{
+    Board, Clock, Color, Effect, GameEvent, GameObserver, Move, MoveRecord, ObserverId, PieceType,
+    Position, Variant,
+};
+
+use std::fmt;
+use std::time::SystemTime;
+
+/// Amount of half moves without captures or pawn moves after which the game is drawn
+const FIFTY_MOVE_RULE: u32 = 100;
+/// Amount of times the same position has to appear for the game to be drawn
+const REPETITIONS: usize = 3;
+
+/// The result of a game, as written in PGN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// 1-0
+    WhiteWins,
+    /// 0-1
+    BlackWins,
+    /// ½-½
+    Draw,
+    /// *, the game is still being played
+    Ongoing,
+}
+
+impl GameResult {
+    /// None for the colors of four-player chess, which FourPlayerGame gives scores instead
+    pub fn win_for(color: Color) -> Option<Self> {
+        match color {
+            Color::White => Some(GameResult::WhiteWins),
+            Color::Black => Some(GameResult::BlackWins),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        };
+        write!(f, "{result}")
+    }
+}
+
+/// Why the game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    Resignation,
+    Timeout,
+    /// Both players agreed to a draw
+    Agreement,
+    /// The same position appeared three times
+    Repetition,
+    /// Fifty moves were played without captures or pawn moves
+    FiftyMoveRule,
+    /// The king was blown up by a capture, in Atomic chess
+    Explosion,
+    /// A player lost every piece, which wins in Antichess
+    AllPiecesLost,
+    /// A player gave check for the third time
+    ThreeChecks,
+    /// A king reached the center of the board
+    KingOfTheHill,
+    /// A king reached the last rank, in Racing Kings
+    RaceFinished,
+    /// The king was captured, in variants without check
+    KingCaptured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    /// The game already has a result
+    GameOver,
+    /// The move can't be played in the current position
+    IllegalMove,
+    /// The move promotes a pawn but no piece was chosen
+    PromotionNotChosen,
+    /// There is no draw offer from the opponent to answer
+    NoDrawOffer,
+    /// The color doesn't play in the game
+    NotAPlayer,
+}
+
+/// What is needed to tell if two positions are the same: the side to move, the pieces on the
+/// board with whether the kings and rooks can still castle, the pieces in the pockets, the
+/// square where a pawn can be captured en passant and the checks given by each player
+type PositionKey = (
+    Color,
+    Vec<(i8, i8, PieceType, Color, bool)>,
+    Vec<(Color, PieceType)>,
+    Option<Position>,
+    (u32, u32),
+);
+
+/// A game between two players, played on a Board
+pub struct Game {
+    board: Board,
+    moves: Vec<MoveRecord>,
+    white: String,
+    black: String,
+    started: SystemTime,
+    result: GameResult,
+    termination: Option<Termination>,
+    /// The player that offered a draw, if any
+    draw_offer: Option<Color>,
+    positions: Vec<PositionKey>,
+    clock: Option<Clock>,
+    observers: Vec<(ObserverId, Box<dyn GameObserver>)>,
+    next_observer: usize,
+}
+
+impl Game {
+    pub fn new(white: impl Into<String>, black: impl Into<String>) -> Self {
+        Game::with_board(Board::default(), white, black)
+    }
+
+    /// Panics on four-player boards, which are played with FourPlayerGame
+    pub fn with_board(board: Board, white: impl Into<String>, black: impl Into<String>) -> Self {
+        assert!(
+            board.get_rules().variant != Variant::FourPlayer,
+            "Four-player chess is played with FourPlayerGame"
+        );
+        let positions = vec![position_key(&board)];
+        let mut game = Game {
+            board,
+            moves: Vec::new(),
+            white: white.into(),
+            black: black.into(),
+            started: SystemTime::now(),
+            result: GameResult::Ongoing,
+            termination: None,
+            draw_offer: None,
+            positions,
+            clock: None,
+            observers: Vec::new(),
+            next_observer: 0,
+        };
+        // The position might already be finished
+        if let Some((result, termination)) = game.board.outcome() {
+            game.end(result, termination);
+        }
+        game
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Only meant for changes that aren't moves, like the pockets in Bughouse
+    pub(crate) fn get_board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    pub fn get_moves(&self) -> &[MoveRecord] {
+        &self.moves
+    }
+
+    /// None for the colors of four-player chess
+    pub fn get_player(&self, color: Color) -> Option<&str> {
+        match color {
+            Color::White => Some(&self.white),
+            Color::Black => Some(&self.black),
+            _ => None,
+        }
+    }
+
+    pub fn get_start_time(&self) -> SystemTime {
+        self.started
+    }
+
+    pub fn get_result(&self) -> GameResult {
+        self.result
+    }
+
+    pub fn get_termination(&self) -> Option<Termination> {
+        self.termination
+    }
+
+    pub fn get_draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result != GameResult::Ongoing
+    }
+
+    pub fn get_clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Plays the game on a clock, the time of the player whose turn it is starts running
+    pub fn set_clock(&mut self, mut clock: Clock) {
+        if !self.is_over() {
+            clock.start(self.board.get_turn());
+        }
+        self.clock = Some(clock);
+    }
+
+    /// Ends the game if the player whose turn it is ran out of time. Returns the flagged player
+    pub fn check_flag(&mut self) -> Option<Color> {
+        if self.is_over() {
+            return None;
+        }
+        let flagged = self.clock.as_ref()?.get_flagged()?;
+        self.end(GameResult::win_for(!flagged)?, Termination::Timeout);
+        Some(flagged)
+    }
+
+    /// Stops the clock, for games that end because of something that happened somewhere else
+    pub(crate) fn stop_clock(&mut self) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop();
+        }
+    }
+
+    /// The observer gets notified of every event from now on
+    pub fn subscribe(&mut self, observer: impl GameObserver + 'static) -> ObserverId {
+        let id = ObserverId(self.next_observer);
+        self.next_observer += 1;
+        self.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    /// Returns false if there was no observer with the given id
+    pub fn unsubscribe(&mut self, id: ObserverId) -> bool {
+        let before = self.observers.len();
+        self.observers.retain(|(observer, _)| *observer != id);
+        self.observers.len() != before
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for (_, observer) in self.observers.iter_mut() {
+            observer.on_event(&event);
+        }
+    }
+
+    /// Plays a move for the player whose turn it is
+    pub fn play(&mut self, mov: Move) -> Result<MoveRecord, GameError> {
+        self.check_flag();
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        match mov.effect {
+            Some(Effect::Promotion(None)) => return Err(GameError::PromotionNotChosen),
+            Some(Effect::Promotion(Some(choice)))
+                if !self.board.get_rules().promotions.contains(&choice) =>
+            {
+                return Err(GameError::IllegalMove);
+            }
+            _ => (),
+        }
+        let is_legal = self
+            .board
+            .get_legal_moves()
+            .into_iter()
+            .any(|legal| same_move(legal, mov));
+        if !is_legal {
+            return Err(GameError::IllegalMove);
+        }
+
+        let record = self.board.execute_move(mov);
+        self.moves.push(record.clone());
+        if let Some(clock) = self.clock.as_mut() {
+            clock.press(record.color);
+        }
+        for event in GameEvent::from_record(&record) {
+            self.emit(event);
+        }
+
+        // Making a move declines the opponent's offer
+        if self.draw_offer == Some(!record.color) {
+            self.draw_offer = None;
+        }
+
+        // Captures and pawn moves can't be undone, so older positions can't be repeated
+        if self.board.get_halfmove_clock() == 0 {
+            self.positions.clear();
+        }
+        let key = position_key(&self.board);
+        let repetitions = self.positions.iter().filter(|pos| **pos == key).count() + 1;
+        self.positions.push(key);
+
+        if let Some((result, termination)) = self.board.outcome() {
+            self.end(result, termination);
+        } else if repetitions >= REPETITIONS {
+            self.end(GameResult::Draw, Termination::Repetition);
+        } else if self.board.get_halfmove_clock() >= FIFTY_MOVE_RULE {
+            self.end(GameResult::Draw, Termination::FiftyMoveRule);
+        }
+
+        Ok(record)
+    }
+
+    pub fn resign(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let result = GameResult::win_for(!color).ok_or(GameError::NotAPlayer)?;
+        self.end(result, Termination::Resignation);
+        Ok(())
+    }
+
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        self.draw_offer = Some(color);
+        Ok(())
+    }
+
+    /// Accepts the draw offered by the opponent of the given color
+    pub fn accept_draw(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if self.draw_offer != Some(!color) {
+            return Err(GameError::NoDrawOffer);
+        }
+        self.end(GameResult::Draw, Termination::Agreement);
+        Ok(())
+    }
+
+    /// Declines the draw offered by the opponent of the given color
+    pub fn decline_draw(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if self.draw_offer != Some(!color) {
+            return Err(GameError::NoDrawOffer);
+        }
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    fn end(&mut self, result: GameResult, termination: Termination) {
+        self.result = result;
+        self.termination = Some(termination);
+        self.draw_offer = None;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop();
+        }
+        self.emit(GameEvent::GameOver {
+            result,
+            termination,
+        });
+    }
+}
+
+/// Promotions are compared without the chosen piece, since the board only generates the bare
+/// promotion
+pub(crate) fn same_move(legal: Move, mov: Move) -> bool {
+    let same_effect = match (legal.effect, mov.effect) {
+        (None, None) => true,
+        (Some(Effect::Capture), Some(Effect::Capture)) => true,
+        (Some(Effect::Promotion(_)), Some(Effect::Promotion(_))) => true,
+        (
+            Some(Effect::Castling { origin, .. }),
+            Some(Effect::Castling {
+                origin: other_origin,
+                ..
+            }),
+        ) => origin == other_origin,
+        (Some(Effect::Drop(piece)), Some(Effect::Drop(other_piece))) => piece == other_piece,
+        (Some(Effect::EnPassant(victim)), Some(Effect::EnPassant(other_victim))) => {
+            victim == other_victim
+        }
+        _ => false,
+    };
+    legal.origin == mov.origin && legal.destination == mov.destination && same_effect
+}
+
+fn position_key(board: &Board) -> PositionKey {
+    let mut pieces: Vec<_> = board
+        .get_pieces()
+        .map(|piece| {
+            let position = piece.get_position();
+            let kind = piece.get_type();
+            // Castling rights are lost once the king or the rook moves
+            let moved = matches!(kind, PieceType::King | PieceType::Rook) && piece.was_moved();
+            (position.x.0, position.y.0, kind, piece.get_color(), moved)
+        })
+        .collect();
+    pieces.sort();
+    let mut pockets: Vec<_> = [Color::White, Color::Black]
+        .into_iter()
+        .flat_map(|color| {
+            board
+                .get_pocket(color)
+                .iter()
+                .map(move |piece| (color, *piece))
+        })
+        .collect();
+    pockets.sort();
+    // Three-check games are closer to their end with every check
+    let checks = (
+        board.get_checks(Color::White),
+        board.get_checks(Color::Black),
+    );
+    (
+        board.get_turn(),
+        pieces,
+        pockets,
+        // Only a capture that can actually be made tells the positions apart
+        board.en_passant_capture(),
+        checks,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ManualTimeSource, Position, TimeControl, Variant};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    fn play(game: &mut Game, origin: (i8, i8), destination: (i8, i8)) -> MoveRecord {
+        let origin = Position::new(origin.0.into(), origin.1.into());
+        let destination = Position::new(destination.0.into(), destination.1.into());
+        let mov = game
+            .get_board()
+            .get_moves_from(origin)
+            .unwrap()
+            .into_iter()
+            .find(|mov| mov.destination == destination)
+            .unwrap();
+        game.play(mov).unwrap()
+    }
+
+    #[test]
+    fn fools_mate_test() {
+        let mut game = Game::new("White", "Black");
+
+        play(&mut game, (5, 1), (5, 2));
+        play(&mut game, (4, 6), (4, 4));
+        play(&mut game, (6, 1), (6, 3));
+        let record = play(&mut game, (3, 7), (7, 3));
+
+        assert!(record.checkmate);
+        assert_eq!(game.get_result(), GameResult::BlackWins);
+        assert_eq!(game.get_termination(), Some(Termination::Checkmate));
+        assert_eq!(game.get_moves().len(), 4);
+        assert_eq!(game.get_result().to_string(), "0-1");
+
+        let mov = Move::new(
+            Position::new(0.into(), 1.into()),
+            Position::new(0.into(), 2.into()),
+            None,
+        );
+        assert_eq!(game.play(mov), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn illegal_move_test() {
+        let mut game = Game::new("White", "Black");
+
+        let mov = Move::new(
+            Position::new(0.into(), 1.into()),
+            Position::new(0.into(), 5.into()),
+            None,
+        );
+        assert_eq!(game.play(mov), Err(GameError::IllegalMove));
+        assert!(game.get_moves().is_empty());
+    }
+
+    #[test]
+    fn resign_test() {
+        let mut game = Game::new("White", "Black");
+
+        game.resign(Color::White).unwrap();
+
+        assert_eq!(game.get_result(), GameResult::BlackWins);
+        assert_eq!(game.get_termination(), Some(Termination::Resignation));
+        assert_eq!(game.resign(Color::Black), Err(GameError::GameOver));
+        assert_eq!(game.get_player(Color::Red), None);
+
+        let mut game = Game::new("White", "Black");
+        assert_eq!(game.resign(Color::Red), Err(GameError::NotAPlayer));
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn draw_offer_test() {
+        let mut game = Game::new("White", "Black");
+
+        assert_eq!(game.accept_draw(Color::Black), Err(GameError::NoDrawOffer));
+
+        game.offer_draw(Color::White).unwrap();
+        // Only the opponent can accept it
+        assert_eq!(game.accept_draw(Color::White), Err(GameError::NoDrawOffer));
+        game.accept_draw(Color::Black).unwrap();
+
+        assert_eq!(game.get_result(), GameResult::Draw);
+        assert_eq!(game.get_termination(), Some(Termination::Agreement));
+        assert_eq!(game.decline_draw(Color::White), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn draw_offer_declined_by_moving_test() {
+        let mut game = Game::new("White", "Black");
+
+        play(&mut game, (4, 1), (4, 3));
+        game.offer_draw(Color::White).unwrap();
+        play(&mut game, (4, 6), (4, 4));
+
+        assert_eq!(game.get_draw_offer(), None);
+        assert_eq!(game.accept_draw(Color::Black), Err(GameError::NoDrawOffer));
+    }
+
+    #[test]
+    fn repetition_test() {
+        let mut game = Game::new("White", "Black");
+
+        for _ in 0..2 {
+            play(&mut game, (6, 0), (5, 2));
+            play(&mut game, (6, 7), (5, 5));
+            play(&mut game, (5, 2), (6, 0));
+            play(&mut game, (5, 5), (6, 7));
+        }
+
+        assert_eq!(game.get_result(), GameResult::Draw);
+        assert_eq!(game.get_termination(), Some(Termination::Repetition));
+    }
+
+    #[test]
+    fn repetition_castling_rights_test() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut game = Game::with_board(board, "White", "Black");
+
+        // The first position could still castle, so it only repeats after three trips
+        for _ in 0..2 {
+            play(&mut game, (4, 0), (4, 1));
+            play(&mut game, (4, 7), (4, 6));
+            play(&mut game, (4, 1), (4, 0));
+            play(&mut game, (4, 6), (4, 7));
+        }
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn timeout_test() {
+        let mut game = Game::new("White", "Black");
+        let time = ManualTimeSource::new();
+        let control = TimeControl::sudden_death(Duration::from_secs(60));
+        game.set_clock(Clock::with_time_source(control, time.clone()));
+
+        time.advance(Duration::from_secs(30));
+        play(&mut game, (4, 1), (4, 3));
+        assert_eq!(game.check_flag(), None);
+
+        time.advance(Duration::from_secs(61));
+        assert_eq!(game.check_flag(), Some(Color::Black));
+        assert_eq!(game.get_result(), GameResult::WhiteWins);
+        assert_eq!(game.get_termination(), Some(Termination::Timeout));
+        // The game keeps the result once the clock is stopped
+        assert_eq!(game.check_flag(), None);
+        assert_eq!(game.get_result(), GameResult::WhiteWins);
+
+        let mov = Move::new(
+            Position::new(4.into(), 6.into()),
+            Position::new(4.into(), 4.into()),
+            None,
+        );
+        assert_eq!(game.play(mov), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn observer_test() {
+        let mut game = Game::new("White", "Black");
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let observed = events.clone();
+        game.subscribe(move |event: &GameEvent| observed.borrow_mut().push(*event));
+
+        play(&mut game, (5, 1), (5, 2));
+        play(&mut game, (4, 6), (4, 4));
+        play(&mut game, (6, 1), (6, 3));
+        events.borrow_mut().clear();
+        play(&mut game, (3, 7), (7, 3));
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                GameEvent::PieceMoved {
+                    piece: PieceType::Queen,
+                    color: Color::Black,
+                    origin: Position::new(3.into(), 7.into()),
+                    destination: Position::new(7.into(), 3.into()),
+                },
+                GameEvent::Check {
+                    color: Color::White
+                },
+                GameEvent::GameOver {
+                    result: GameResult::BlackWins,
+                    termination: Termination::Checkmate,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unsubscribe_test() {
+        let mut game = Game::new("White", "Black");
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let observed = events.clone();
+        let id = game.subscribe(move |event: &GameEvent| observed.borrow_mut().push(*event));
+
+        play(&mut game, (4, 1), (4, 3));
+        assert!(game.unsubscribe(id));
+        assert!(!game.unsubscribe(id));
+        play(&mut game, (4, 6), (4, 4));
+
+        assert_eq!(events.borrow().len(), 1);
+    }
+
+    #[test]
+    fn three_check_test() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3 +2+0";
+        let board = Board::from_fen(fen).unwrap();
+        let mut game = Game::with_board(board, "White", "Black");
+
+        let record = play(&mut game, (5, 0), (1, 4));
+
+        assert!(record.check);
+        assert_eq!(game.get_board().get_checks(Color::White), 3);
+        assert_eq!(game.get_result(), GameResult::WhiteWins);
+        assert_eq!(game.get_termination(), Some(Termination::ThreeChecks));
+    }
+
+    #[test]
+    fn three_check_repetition_test() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +0+0";
+        let board = Board::from_fen(fen).unwrap();
+        let mut game = Game::with_board(board, "White", "Black");
+
+        // Every trip gives a check, so the positions aren't the same
+        for _ in 0..2 {
+            play(&mut game, (0, 0), (0, 7));
+            play(&mut game, (4, 7), (4, 6));
+            play(&mut game, (0, 7), (0, 0));
+            play(&mut game, (4, 6), (4, 7));
+        }
+        assert!(!game.is_over());
+        assert_eq!(game.get_board().get_checks(Color::White), 2);
+    }
+
+    #[test]
+    fn king_of_the_hill_test() {
+        let mut board = Board::from_fen("8/8/8/8/8/4K3/8/k7 w - - 0 1").unwrap();
+        board.set_rules(Variant::KingOfTheHill.rules());
+        let mut game = Game::with_board(board, "White", "Black");
+
+        play(&mut game, (4, 2), (4, 3));
+
+        assert_eq!(game.get_result(), GameResult::WhiteWins);
+        assert_eq!(game.get_termination(), Some(Termination::KingOfTheHill));
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    Board, ChoiceOfPromotablePiece, Color, Effect, Game, GameError, GameResult, Move, PieceType,
+    Position, Termination,
+};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Line along which a king is being checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckDirection {
+    Rank,
+    File,
+    /// The longer of the two diagonals that go through the king
+    LongDiagonal,
+    /// The shorter of the two diagonals that go through the king
+    ShortDiagonal,
+    Knight,
+}
+
+/// Something the referee tells a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Announcement {
+    /// The move can't be played, the player has to try another one. Only the player that tried
+    /// it is told
+    Illegal,
+    /// The player of the given color made a move
+    Moved { color: Color },
+    /// A piece was captured on the square, without saying which one
+    Capture { square: Position },
+    /// The king of the given color is in check
+    Check {
+        color: Color,
+        direction: CheckDirection,
+    },
+    /// The player of the given color can capture with a pawn in this many ways
+    PawnTries { color: Color, tries: usize },
+    GameOver {
+        result: GameResult,
+        termination: Termination,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefereeError {
+    /// It's the turn of the other player
+    NotYourTurn,
+    /// The game already has a result
+    GameOver,
+}
+
+impl fmt::Display for RefereeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefereeError::NotYourTurn => write!(f, "it's the turn of the other player"),
+            RefereeError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for RefereeError {}
+
+/// Referee of a game of Kriegspiel. Neither player sees the pieces of the other one, they try
+/// moves blindly and only learn what the referee announces to them
+pub struct Referee {
+    game: Game,
+    messages: BTreeMap<Color, Vec<Announcement>>,
+}
+
+impl Referee {
+    pub fn new(white: impl Into<String>, black: impl Into<String>) -> Self {
+        Referee::with_board(Board::default(), white, black)
+    }
+
+    pub fn with_board(board: Board, white: impl Into<String>, black: impl Into<String>) -> Self {
+        let mut referee = Referee {
+            game: Game::with_board(board, white, black),
+            messages: BTreeMap::new(),
+        };
+        referee.announce_pawn_tries();
+        referee
+    }
+
+    /// The whole game, which only the referee can see
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Everything the player of the given color was told, oldest first
+    pub fn get_messages(&self, color: Color) -> &[Announcement] {
+        self.messages.get(&color).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Returns the messages that the player of the given color hasn't taken yet
+    pub fn take_messages(&mut self, color: Color) -> Vec<Announcement> {
+        self.messages.remove(&color).unwrap_or_default()
+    }
+
+    fn tell(&mut self, color: Color, announcement: Announcement) {
+        self.messages.entry(color).or_default().push(announcement);
+    }
+
+    fn tell_both(&mut self, announcement: Announcement) {
+        self.tell(Color::White, announcement);
+        self.tell(Color::Black, announcement);
+    }
+
+    /// Tries a move for the given player. Only the origin and the destination matter, along with
+    /// the promoted piece, which defaults to a queen. Castling can also be tried with the square
+    /// of the rook as the destination, see Move::select. Returns false if the move is illegal, in
+    /// which case the player is told so and has to try again
+    pub fn try_move(&mut self, color: Color, mov: Move) -> Result<bool, RefereeError> {
+        if self.game.is_over() {
+            return Err(RefereeError::GameOver);
+        }
+        if self.game.get_board().get_turn() != color {
+            return Err(RefereeError::NotYourTurn);
+        }
+
+        let choice = match mov.effect {
+            Some(Effect::Promotion(Some(choice))) => choice,
+            _ => ChoiceOfPromotablePiece::Queen,
+        };
+        let candidates: Vec<_> = self
+            .game
+            .get_board()
+            .get_legal_moves()
+            .into_iter()
+            .filter(|legal| legal.origin == mov.origin)
+            .collect();
+        let legal = Move::select(&candidates, mov.destination).map(|legal| match legal.effect {
+            Some(Effect::Promotion(None)) => Move {
+                effect: Some(Effect::Promotion(Some(choice))),
+                ..legal
+            },
+            _ => legal,
+        });
+        let Some(legal) = legal else {
+            self.tell(color, Announcement::Illegal);
+            return Ok(false);
+        };
+
+        let record = match self.game.play(legal) {
+            Ok(record) => record,
+            Err(GameError::GameOver) => return Err(RefereeError::GameOver),
+            // A promotion to a piece the rules don't allow
+            Err(_) => {
+                self.tell(color, Announcement::Illegal);
+                return Ok(false);
+            }
+        };
+
+        self.tell(!color, Announcement::Moved { color });
+        if record.captured.is_some() {
+            // The square of the captured piece, which for en passant isn't the destination
+            let square = match record.mov.effect {
+                Some(Effect::EnPassant(victim)) => victim,
+                _ => record.mov.destination,
+            };
+            self.tell_both(Announcement::Capture { square });
+        }
+        for direction in self.check_directions(!color) {
+            self.tell_both(Announcement::Check {
+                color: !color,
+                direction,
+            });
+        }
+
+        if self.game.is_over() {
+            if let Some(termination) = self.game.get_termination() {
+                self.tell_both(Announcement::GameOver {
+                    result: self.game.get_result(),
+                    termination,
+                });
+            }
+        } else {
+            self.announce_pawn_tries();
+        }
+
+        Ok(true)
+    }
+
+    /// Tells both players how many pawn captures the player to move has, if there are any
+    fn announce_pawn_tries(&mut self) {
+        let board = self.game.get_board();
+        let color = board.get_turn();
+        let pawns: Vec<_> = board
+            .find_pieces(Some(PieceType::Pawn), Some(color))
+            .map(|pawn| pawn.get_position())
+            .collect();
+        let tries = board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mov| pawns.contains(&mov.origin) && mov.origin.x != mov.destination.x)
+            .count();
+
+        if tries > 0 {
+            self.tell_both(Announcement::PawnTries { color, tries });
+        }
+    }
+
+    /// Directions from which the king of the given color is being checked
+    fn check_directions(&self, color: Color) -> Vec<CheckDirection> {
+        let board = self.game.get_board();
+        let Some(king) = board
+            .find_pieces(Some(PieceType::King), Some(color))
+            .map(|king| king.get_position())
+            .next()
+        else {
+            return Vec::new();
+        };
+
+        board
+            .find_pieces(None, Some(!color))
+            .filter(|piece| {
+                piece
+                    .available_positions(board)
+                    .iter()
+                    .any(|mov| mov.destination == king)
+            })
+            .map(|piece| {
+                let checker = piece.get_position();
+                let (dx, dy) = (checker.x.0 - king.x.0, checker.y.0 - king.y.0);
+                // Pieces like the archbishop can check from a line, or with a knight leap
+                if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+                    CheckDirection::Knight
+                } else if checker.y == king.y {
+                    CheckDirection::Rank
+                } else if checker.x == king.x {
+                    CheckDirection::File
+                } else {
+                    diagonal_direction(board, king, checker)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether the checker stands on the long or the short diagonal of the king
+fn diagonal_direction(board: &Board, king: Position, checker: Position) -> CheckDirection {
+    let (width, height) = board.get_dimensions();
+    let (x, y) = (king.x.0, king.y.0);
+    let (right, top) = (width as i8 - 1 - x, height as i8 - 1 - y);
+
+    // Squares on each diagonal, going through the king
+    let rising = x.min(y) + right.min(top) + 1;
+    let falling = x.min(top) + right.min(y) + 1;
+
+    let on_rising = (checker.x.0 - x).signum() == (checker.y.0 - y).signum();
+    let (this, other) = if on_rising {
+        (rising, falling)
+    } else {
+        (falling, rising)
+    };
+    if this >= other {
+        CheckDirection::LongDiagonal
+    } else {
+        CheckDirection::ShortDiagonal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mov(origin: (i8, i8), destination: (i8, i8)) -> Move {
+        Move::new(
+            Position::new(origin.0.into(), origin.1.into()),
+            Position::new(destination.0.into(), destination.1.into()),
+            None,
+        )
+    }
+
+    fn referee(fen: &str) -> Referee {
+        Referee::with_board(Board::from_fen(fen).unwrap(), "White", "Black")
+    }
+
+    #[test]
+    fn illegal_move_test() {
+        let mut referee = Referee::new("White", "Black");
+
+        assert_eq!(
+            referee.try_move(Color::White, mov((4, 1), (4, 4))),
+            Ok(false)
+        );
+        assert_eq!(
+            referee.try_move(Color::Black, mov((4, 6), (4, 4))),
+            Err(RefereeError::NotYourTurn)
+        );
+
+        assert_eq!(referee.get_messages(Color::White), &[Announcement::Illegal]);
+        assert!(referee.get_messages(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn castling_on_the_rook_test() {
+        let mut referee = referee("1k6/8/8/8/8/8/8/RK6 w A - 0 1");
+        assert_eq!(
+            referee.try_move(Color::White, mov((1, 0), (0, 0))),
+            Ok(true)
+        );
+        let king = referee
+            .get_game()
+            .get_board()
+            .find_pieces(Some(PieceType::King), Some(Color::White))
+            .map(|king| king.get_position())
+            .next();
+        assert_eq!(king, Some(Position::new(2.into(), 0.into())));
+    }
+
+    #[test]
+    fn capture_and_pawn_tries_test() {
+        let mut referee = Referee::new("White", "Black");
+
+        assert_eq!(
+            referee.try_move(Color::White, mov((4, 1), (4, 3))),
+            Ok(true)
+        );
+        assert_eq!(
+            referee.take_messages(Color::Black),
+            vec![Announcement::Moved {
+                color: Color::White
+            }]
+        );
+
+        assert_eq!(
+            referee.try_move(Color::Black, mov((3, 6), (3, 4))),
+            Ok(true)
+        );
+        let tries = Announcement::PawnTries {
+            color: Color::White,
+            tries: 1,
+        };
+        assert_eq!(
+            referee.take_messages(Color::White),
+            vec![
+                Announcement::Moved {
+                    color: Color::Black
+                },
+                tries
+            ]
+        );
+        assert_eq!(referee.take_messages(Color::Black), vec![tries]);
+
+        assert_eq!(
+            referee.try_move(Color::White, mov((4, 3), (3, 4))),
+            Ok(true)
+        );
+        let capture = Announcement::Capture {
+            square: Position::new(3.into(), 4.into()),
+        };
+        assert!(referee.get_messages(Color::White).contains(&capture));
+        assert!(referee.get_messages(Color::Black).contains(&capture));
+    }
+
+    fn check_direction(fen: &str, origin: (i8, i8), destination: (i8, i8)) -> CheckDirection {
+        let mut referee = referee(fen);
+        assert_eq!(
+            referee.try_move(Color::White, mov(origin, destination)),
+            Ok(true)
+        );
+
+        let checks: Vec<_> = referee
+            .get_messages(Color::Black)
+            .iter()
+            .filter_map(|announcement| match announcement {
+                Announcement::Check { color, direction } => {
+                    assert_eq!(*color, Color::Black);
+                    Some(*direction)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(checks.len(), 1);
+        checks[0]
+    }
+
+    #[test]
+    fn check_directions_test() {
+        let rank = check_direction("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", (0, 0), (0, 7));
+        assert_eq!(rank, CheckDirection::Rank);
+
+        let file = check_direction("3k4/8/8/8/8/8/8/K6R w - - 0 1", (7, 0), (3, 0));
+        assert_eq!(file, CheckDirection::File);
+
+        let knight = check_direction("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1", (4, 3), (3, 5));
+        assert_eq!(knight, CheckDirection::Knight);
+
+        // Five squares go from a4 to e8, four from h5 to e8
+        let long = check_direction("4k3/8/8/8/8/8/8/3BK3 w - - 0 1", (3, 0), (0, 3));
+        assert_eq!(long, CheckDirection::LongDiagonal);
+
+        let short = check_direction("4k3/8/8/8/8/8/4B3/4K3 w - - 0 1", (4, 1), (7, 4));
+        assert_eq!(short, CheckDirection::ShortDiagonal);
+    }
+
+    #[test]
+    fn game_over_test() {
+        let mut referee = referee("k7/8/1K6/8/8/8/8/7R w - - 0 1");
+
+        assert_eq!(
+            referee.try_move(Color::White, mov((7, 0), (7, 7))),
+            Ok(true)
+        );
+
+        let game_over = Announcement::GameOver {
+            result: GameResult::WhiteWins,
+            termination: Termination::Checkmate,
+        };
+        assert_eq!(referee.get_messages(Color::Black).last(), Some(&game_over));
+        assert_eq!(
+            referee.try_move(Color::Black, mov((0, 7), (1, 7))),
+            Err(RefereeError::GameOver)
+        );
+    }
+}
@@ -1,15 +1,41 @@
 mod board;
+mod bughouse;
+mod clock;
+mod definition;
+mod event;
+mod fen;
+mod four_player;
+mod game;
+mod kriegspiel;
+mod notation;
 mod piece;
 mod pieces;
+mod rules;
 
-pub use board::Board;
+pub use board::{Board, MAX_SIZE};
+pub use bughouse::{Bughouse, BughouseBoard, MatchResult, Team};
+pub use clock::{
+    Clock, Increment, ManualTimeSource, ParseTimeControlError, Stage, SystemTimeSource,
+    TimeControl, TimeSource,
+};
+pub use definition::{DefinitionError, VariantDefinition};
+pub use event::{GameEvent, GameObserver, ObserverId};
+pub use fen::{FenError, STARTING_POSITION};
+pub use four_player::FourPlayerGame;
+pub use game::{Game, GameError, GameResult, Termination};
+pub use kriegspiel::{Announcement, CheckDirection, Referee, RefereeError};
+pub use notation::NotationError;
+pub use piece::ChoiceOfPromotablePiece;
 pub use piece::Colored;
 pub use piece::CurrentPosition;
 pub use piece::Effect;
 pub use piece::Move;
+pub use piece::MoveRecord;
 pub use piece::Moveset;
 pub use piece::Recognizable;
 pub use piece::{BottomLeft, BottomRight, UpperLeft, UpperRight};
 pub use piece::{Color, Position};
 pub use piece::{Piece, PieceType};
 pub use piece::{XAxis, YAxis};
+pub use pieces::{Betza, BetzaError, FairyPiece};
+pub use rules::{ParseVariantError, Rules, Variant};
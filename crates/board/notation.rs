@@ -0,0 +1,227 @@
+use crate::fen::piece_to_char;
+use crate::{Board, ChoiceOfPromotablePiece, Color, Effect, Move, Position, XAxis, YAxis};
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    /// The text isn't a move in coordinate notation
+    Invalid(String),
+    /// The move is well written but can't be played in the position
+    Illegal(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Invalid(text) => write!(f, "invalid move: {text}"),
+            NotationError::Illegal(text) => write!(f, "illegal move: {text}"),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// Name of the square, like e4. Ranks past the ninth take two digits, like a10
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = (b'a' + self.x.0 as u8) as char;
+        write!(f, "{file}{}", self.y.0 + 1)
+    }
+}
+
+/// The move in coordinate notation, as used by UCI: e2e4, e7e8q, e1g1 or P@e4
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_uci(false))
+    }
+}
+
+impl Move {
+    /// The move in coordinate notation. When chess960 is true castling is written as the king
+    /// taking its own rook, like e1h1, since in Chess960 the king may not move at all
+    pub fn to_uci(&self, chess960: bool) -> String {
+        match self.effect {
+            Some(Effect::Drop(piece)) => {
+                format!(
+                    "{}@{}",
+                    piece_to_char(piece, Color::White),
+                    self.destination
+                )
+            }
+            Some(Effect::Castling { origin, .. }) if chess960 => {
+                format!("{}{}", self.origin, origin)
+            }
+            Some(Effect::Promotion(Some(choice))) => {
+                format!(
+                    "{}{}{}",
+                    self.origin,
+                    self.destination,
+                    promotion_char(choice)
+                )
+            }
+            _ => format!("{}{}", self.origin, self.destination),
+        }
+    }
+}
+
+fn promotion_char(choice: ChoiceOfPromotablePiece) -> char {
+    match choice {
+        ChoiceOfPromotablePiece::Bishop => 'b',
+        ChoiceOfPromotablePiece::Knight => 'n',
+        ChoiceOfPromotablePiece::Queen => 'q',
+        ChoiceOfPromotablePiece::Rook => 'r',
+        ChoiceOfPromotablePiece::King => 'k',
+        ChoiceOfPromotablePiece::Archbishop => 'a',
+        ChoiceOfPromotablePiece::Chancellor => 'c',
+        ChoiceOfPromotablePiece::Fairy(letter) => letter,
+    }
+}
+
+/// Reads a square from the start of the text, returning it with the rest of the text
+pub(crate) fn parse_square(text: &str) -> Option<(Position, &str)> {
+    let mut chars = text.chars();
+    let file = chars.next().filter(char::is_ascii_lowercase)?;
+    let rest = chars.as_str();
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rank: i8 = rest[..digits].parse().ok()?;
+    if rank < 1 {
+        return None;
+    }
+    let position = Position::new(XAxis((file as u8 - b'a') as i8), YAxis(rank - 1));
+    Some((position, &rest[digits..]))
+}
+
+impl Board {
+    /// Finds the legal move written in coordinate notation, like e2e4, e7e8q or P@e4. Castling
+    /// can be written with the destination of the king, e1g1, or as the king taking its own
+    /// rook, e1h1, as in Chess960. When a plain king move has the same destination, only the
+    /// second way means castling
+    pub fn parse_move(&self, text: &str) -> Result<Move, NotationError> {
+        let invalid = || NotationError::Invalid(text.to_string());
+        let illegal = || NotationError::Illegal(text.to_string());
+        let legal_moves = self.get_legal_moves();
+
+        if let Some((piece, square)) = text.split_once('@') {
+            let mut letters = piece.chars();
+            let letter = letters.next().ok_or_else(invalid)?;
+            let (destination, rest) = parse_square(square).ok_or_else(invalid)?;
+            if letters.next().is_some() || !rest.is_empty() {
+                return Err(invalid());
+            }
+            return legal_moves
+                .into_iter()
+                .find(|mov| {
+                    mov.destination == destination
+                        && matches!(mov.effect, Some(Effect::Drop(dropped))
+                            if piece_to_char(dropped, Color::Black) == letter.to_ascii_lowercase())
+                })
+                .ok_or_else(illegal);
+        }
+
+        let (origin, rest) = parse_square(text).ok_or_else(invalid)?;
+        let (destination, rest) = parse_square(rest).ok_or_else(invalid)?;
+        let mut letters = rest.chars();
+        let promotion = letters.next();
+        if letters.next().is_some() {
+            return Err(invalid());
+        }
+
+        let candidates: Vec<_> = legal_moves
+            .into_iter()
+            .filter(|mov| mov.origin == origin && !matches!(mov.effect, Some(Effect::Drop(_))))
+            .collect();
+        let mov = Move::select(&candidates, destination).ok_or_else(illegal)?;
+
+        match (mov.effect, promotion) {
+            (Some(Effect::Promotion(_)), Some(letter)) => self
+                .get_rules()
+                .promotions
+                .iter()
+                .find(|choice| promotion_char(**choice) == letter.to_ascii_lowercase())
+                .map(|choice| Move {
+                    effect: Some(Effect::Promotion(Some(*choice))),
+                    ..mov
+                })
+                .ok_or_else(illegal),
+            (Some(Effect::Promotion(_)), None) | (_, Some(_)) => Err(illegal()),
+            (_, None) => Ok(mov),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PieceType;
+
+    #[test]
+    fn square_names_test() {
+        assert_eq!(Position::new(XAxis(4), YAxis(3)).to_string(), "e4");
+        assert_eq!(Position::new(XAxis(0), YAxis(9)).to_string(), "a10");
+        assert_eq!(
+            parse_square("j10"),
+            Some((Position::new(XAxis(9), YAxis(9)), ""))
+        );
+        assert_eq!(parse_square("e0"), None);
+        assert_eq!(parse_square("E4"), None);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let board = Board::default();
+        for mov in board.get_legal_moves() {
+            assert_eq!(board.parse_move(&mov.to_string()), Ok(mov));
+        }
+        assert_eq!(
+            board.parse_move("e2e5"),
+            Err(NotationError::Illegal("e2e5".to_string()))
+        );
+        assert_eq!(
+            board.parse_move("e2"),
+            Err(NotationError::Invalid("e2".to_string()))
+        );
+    }
+
+    #[test]
+    fn promotion_test() {
+        let board = Board::from_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1").unwrap();
+        let mov = board.parse_move("e7e8n").unwrap();
+        assert_eq!(
+            mov.effect,
+            Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Knight)))
+        );
+        assert_eq!(mov.to_string(), "e7e8n");
+        // The piece must be chosen, and only promotions can choose one
+        assert!(board.parse_move("e7e8").is_err());
+        assert!(board.parse_move("e1d1q").is_err());
+    }
+
+    #[test]
+    fn castling_test() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let short = board.parse_move("e1g1").unwrap();
+        assert!(matches!(short.effect, Some(Effect::Castling { .. })));
+        assert_eq!(board.parse_move("e1h1"), Ok(short));
+        assert_eq!(short.to_uci(false), "e1g1");
+        assert_eq!(short.to_uci(true), "e1h1");
+
+        // The king can step to c1 or castle there, castling takes the square of the rook
+        let board = Board::from_fen("1k6/8/8/8/8/8/8/RK6 w A - 0 1").unwrap();
+        let step = board.parse_move("b1c1").unwrap();
+        assert_eq!(step.effect, None);
+        let long = board.parse_move("b1a1").unwrap();
+        assert!(matches!(long.effect, Some(Effect::Castling { .. })));
+        assert_eq!(long.destination, step.destination);
+    }
+
+    #[test]
+    fn drop_test() {
+        let fen = "rnbqkbnr/ppp1pppp/8/8/8/8/PPPPPPPP/RNBQKBNR[P] w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        let mov = board.parse_move("P@e4").unwrap();
+        assert_eq!(mov.effect, Some(Effect::Drop(PieceType::Pawn)));
+        assert_eq!(mov.to_string(), "P@e4");
+        assert!(board.parse_move("Q@e4").is_err());
+    }
+}
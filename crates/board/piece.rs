@@ -14,7 +14,7 @@ impl From<UpperLeft> for Position {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PieceType {
     Bishop,
     King,
@@ -22,21 +22,58 @@ pub enum PieceType {
     Pawn,
     Queen,
     Rook,
+    /// Moves like a bishop and a knight
+    Archbishop,
+    /// Moves like a rook and a knight
+    Chancellor,
+    /// A piece defined by its Betza notation, known by its lowercase letter
+    Fairy(char),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChoiceOfPromotablePiece {
     Bishop,
     Knight,
     Queen,
     Rook,
+    /// Only in variants where the king is an ordinary piece
+    King,
+    Archbishop,
+    Chancellor,
+    /// A fairy piece of the rules, by its letter
+    Fairy(char),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
 pub enum Color {
     Black,
     White,
+    /// Four-player chess: sits at the bottom of the board and moves first
+    Red,
+    /// Four-player chess: sits on the left side
+    Blue,
+    /// Four-player chess: sits at the top
+    Yellow,
+    /// Four-player chess: sits on the right side
+    Green,
+}
+
+impl Color {
+    /// Players of four-player chess, in the order they move
+    pub const FOUR_PLAYERS: [Color; 4] = [Color::Red, Color::Blue, Color::Yellow, Color::Green];
+
+    /// Direction the pawns of this color advance in, as (x, y)
+    pub fn forward(self) -> (i8, i8) {
+        match self {
+            Color::White | Color::Red => (0, 1),
+            Color::Black | Color::Yellow => (0, -1),
+            Color::Blue => (1, 0),
+            Color::Green => (-1, 0),
+        }
+    }
 }
+
+/// The opponent, or the player sitting across the board in four-player chess
 impl Not for Color {
     type Output = Self;
 
@@ -44,6 +81,10 @@ impl Not for Color {
         match self {
             Color::White => Color::Black,
             Color::Black => Color::White,
+            Color::Red => Color::Yellow,
+            Color::Yellow => Color::Red,
+            Color::Blue => Color::Green,
+            Color::Green => Color::Blue,
         }
     }
 }
@@ -187,7 +228,7 @@ impl Position {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Effect {
     /// Capture a piece in the board
     Capture,
@@ -200,10 +241,16 @@ pub enum Effect {
     },
     /// A pawn is promoted. None means that a choice wasnt made yet
     Promotion(Option<ChoiceOfPromotablePiece>),
+    /// A piece from the pocket is placed on the board. Both the origin and the destination of
+    /// the move are the square where it's placed
+    Drop(PieceType),
+    /// A pawn captures en passant the pawn standing on the given square, which just advanced two
+    /// squares past it
+    EnPassant(Position),
 }
 
 /// This represent a move done by a piece. This means
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Move {
     /// The place where the piece that will execute the move is standing on
     pub origin: Position,
@@ -221,6 +268,46 @@ impl Move {
             effect,
         }
     }
+
+    /// Picks the move that goes to the square among the moves of a piece. When castling and a
+    /// plain king move end on the same square, the plain move is picked, and castling is picked
+    /// with the square of the other piece instead: the rook, or the king when the rook moves
+    pub fn select(moves: &[Move], square: Position) -> Option<Move> {
+        let castling_with = |mov: &&Move| match mov.effect {
+            Some(Effect::Castling { origin, .. }) => Some(origin),
+            _ => None,
+        };
+        moves
+            .iter()
+            .find(|mov| mov.destination == square && castling_with(mov).is_none())
+            .or_else(|| moves.iter().find(|mov| castling_with(mov) == Some(square)))
+            .or_else(|| moves.iter().find(|mov| mov.destination == square))
+            .copied()
+    }
+}
+
+/// Everything that happened on the board when a Move was executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveRecord {
+    /// The move that was executed
+    pub mov: Move,
+    /// The type of the piece that executed the move, before any promotion
+    pub piece: PieceType,
+    /// The color of the piece that executed the move
+    pub color: Color,
+    /// The piece that was removed from the board, if any
+    pub captured: Option<(PieceType, Color)>,
+    /// Pieces that the explosion of an Atomic capture removed besides the captured one: the
+    /// capturing piece and the pieces around it that aren't pawns
+    pub exploded: Vec<(PieceType, Color, Position)>,
+    /// The piece a pawn was promoted to, if any
+    pub promotion: Option<ChoiceOfPromotablePiece>,
+    /// Origin and destination of the rook when castling
+    pub castling: Option<(Position, Position)>,
+    /// The opponent's king is in check after the move
+    pub check: bool,
+    /// The opponent's king is in check and there are no legal moves left
+    pub checkmate: bool,
 }
 
 // TODO: Remove all these traits, move to "piece" trait
@@ -244,11 +331,28 @@ pub trait Recognizable {
     fn get_type(&self) -> PieceType;
 }
 
-/// The piece can execute a Move
-pub trait Piece: Colored + Moveset + Recognizable + CurrentPosition {
+/// The piece can execute a Move. Boards are searched on other threads, so pieces are Send
+pub trait Piece: Colored + Moveset + Recognizable + CurrentPosition + Send + Sync {
     fn was_moved(&self) -> bool {
         todo!()
     }
+
+    /// Only pieces that keep track of their first move need this
+    fn set_moved(&mut self, _moved: bool) {}
+
+    /// The piece started the game as a pawn
+    fn was_promoted(&self) -> bool {
+        false
+    }
+
+    /// Needed to simulate moves on a copy of the board
+    fn box_clone(&self) -> Box<dyn Piece>;
+}
+
+impl Clone for Box<dyn Piece> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
 }
 // trait Sub: Super {}
 // trait Super {}
@@ -0,0 +1,55 @@
+use crate::{
+    Board, Color, Colored, CurrentPosition, Move, Moveset, Piece, PieceType, Position, Recognizable,
+};
+
+use super::{Bishop, Knight};
+
+// ================================ Archbishop ==================================
+/// Moves like a bishop and like a knight
+#[derive(Clone)]
+pub struct Archbishop {
+    color: Color,
+    position: Position,
+}
+
+impl Archbishop {
+    pub fn new(color: Color, position: Position) -> Self {
+        Self { color, position }
+    }
+}
+
+impl CurrentPosition for Archbishop {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Recognizable for Archbishop {
+    fn get_type(&self) -> PieceType {
+        PieceType::Archbishop
+    }
+}
+
+impl Colored for Archbishop {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+impl Moveset for Archbishop {
+    fn move_to(&mut self, destination: Position) {
+        self.position = destination;
+    }
+
+    fn available_positions(&self, board: &Board) -> Vec<Move> {
+        let mut moves = Bishop::new(self.color, self.position).available_positions(board);
+        moves.extend(Knight::new(self.color, self.position).available_positions(board));
+        moves
+    }
+}
+
+impl Piece for Archbishop {
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
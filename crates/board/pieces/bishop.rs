@@ -5,6 +5,7 @@ use crate::{
 use std::collections::HashSet;
 
 // ================================= Bishop ====================================
+#[derive(Clone)]
 pub struct Bishop {
     color: Color,
     position: Position,
@@ -80,4 +81,8 @@ impl Moveset for Bishop {
     }
 }
 
-impl Piece for Bishop {}
+impl Piece for Bishop {
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    Board, Color, Colored, CurrentPosition, Move, Moveset, Piece, PieceType, Position, Recognizable,
+};
+
+use super::{Knight, Rook};
+
+// ================================ Chancellor ==================================
+/// Moves like a rook and like a knight
+#[derive(Clone)]
+pub struct Chancellor {
+    color: Color,
+    position: Position,
+}
+
+impl Chancellor {
+    pub fn new(color: Color, position: Position) -> Self {
+        Self { color, position }
+    }
+}
+
+impl CurrentPosition for Chancellor {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Recognizable for Chancellor {
+    fn get_type(&self) -> PieceType {
+        PieceType::Chancellor
+    }
+}
+
+impl Colored for Chancellor {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+impl Moveset for Chancellor {
+    fn move_to(&mut self, destination: Position) {
+        self.position = destination;
+    }
+
+    fn available_positions(&self, board: &Board) -> Vec<Move> {
+        let mut moves = Rook::new(self.color, self.position).available_positions(board);
+        moves.extend(Knight::new(self.color, self.position).available_positions(board));
+        moves
+    }
+}
+
+impl Piece for Chancellor {
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,679 @@
+use crate::{
+    Board, Color, Colored, CurrentPosition, Effect, Move, Moveset, Piece, PieceType, Position,
+    Recognizable, XAxis, YAxis,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// ================================== Betza ===================================
+/// Which moves a piece can make, written in Betza notation. Uppercase letters are the basic
+/// moves (atoms), and the lowercase letters before them restrict those moves. Writing an atom
+/// twice turns it into a rider, and a number after it limits how far it rides:
+///
+/// - W, F: one step orthogonally or diagonally. D, A, H, G: leaps of two or three squares in
+///   those same lines. N, C, Z: the (1,2), (1,3) and (2,3) leaps
+/// - K = WF, R = WW, B = FF and Q = WWFF
+/// - f, b, l, r: forward, backward, left and right. v and s keep the moves that go mostly
+///   vertically or sideways. Two letters combine for diagonal and oblique atoms, so fl is
+///   forward and left, and ff are the narrow forward moves of a knight
+/// - m: only to empty squares. c: only to capture. i: only if the piece hasn't moved yet.
+///   n: the leap can be blocked, like the double step of a pawn. e: captures en passant a pawn
+///   that just advanced two squares
+///
+/// For example, the pawn is fmWfceFifmnD and the archbishop is BN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Betza {
+    notation: String,
+    atoms: Vec<Atom>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BetzaError {
+    /// The notation has no moves at all
+    Empty,
+    /// A letter this notation doesn't support, like the hoppers of XBetza
+    UnknownLetter(char),
+    /// Modifiers at the end of the notation, that don't apply to any atom
+    DanglingModifiers(String),
+    /// A range that isn't a number between 1 and 255
+    Range(String),
+}
+
+impl fmt::Display for BetzaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BetzaError::Empty => write!(f, "no moves in the notation"),
+            BetzaError::UnknownLetter(letter) => write!(f, "unknown letter: {letter}"),
+            BetzaError::DanglingModifiers(modifiers) => {
+                write!(f, "modifiers without a move: {modifiers}")
+            }
+            BetzaError::Range(range) => write!(f, "invalid range: {range}"),
+        }
+    }
+}
+
+impl std::error::Error for BetzaError {}
+
+/// Squares a move can go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Any,
+    /// Only empty squares
+    Move,
+    /// Only squares with an opponent
+    Capture,
+}
+
+/// Restriction on the direction of a move. Every field that is set has to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Direction {
+    /// Towards the opponent, or away from them
+    forward: Option<bool>,
+    /// To the right or to the left, from the point of view of the owner
+    right: Option<bool>,
+    /// Mostly vertical, or mostly sideways
+    vertical: Option<bool>,
+}
+
+impl Direction {
+    fn matches(&self, dx: i8, dy: i8) -> bool {
+        let forward = if dy > 0 {
+            Some(true)
+        } else {
+            (dy < 0).then_some(false)
+        };
+        let right = if dx > 0 {
+            Some(true)
+        } else {
+            (dx < 0).then_some(false)
+        };
+        let vertical = match dy.abs().cmp(&dx.abs()) {
+            std::cmp::Ordering::Greater => Some(true),
+            std::cmp::Ordering::Less => Some(false),
+            std::cmp::Ordering::Equal => None,
+        };
+        self.forward.is_none_or(|wanted| forward == Some(wanted))
+            && self.right.is_none_or(|wanted| right == Some(wanted))
+            && self.vertical.is_none_or(|wanted| vertical == Some(wanted))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Atom {
+    /// The longest and the shortest side of the leap
+    leap: (i8, i8),
+    /// How many times the leap can be repeated in a line. None means there's no limit
+    range: Option<u8>,
+    /// Empty means every direction
+    directions: Vec<Direction>,
+    mode: Mode,
+    initial: bool,
+    lame: bool,
+    en_passant: bool,
+}
+
+impl Atom {
+    /// Every move of the atom, with forward being positive y
+    fn steps(&self) -> Vec<(i8, i8)> {
+        let (long, short) = self.leap;
+        let mut steps = Vec::new();
+        for (a, b) in [(long, short), (short, long)] {
+            for (x, y) in [(a, b), (-a, b), (a, -b), (-a, -b)] {
+                if !steps.contains(&(x, y)) {
+                    steps.push((x, y));
+                }
+            }
+        }
+        steps
+            .into_iter()
+            .filter(|(x, y)| {
+                self.directions.is_empty()
+                    || self
+                        .directions
+                        .iter()
+                        .any(|direction| direction.matches(*x, *y))
+            })
+            .collect()
+    }
+
+    /// Orthogonal and diagonal riders move like the rook and the bishop do
+    fn is_slider(&self) -> bool {
+        matches!(self.leap, (1, 0) | (1, 1)) && self.range != Some(1) && self.mode == Mode::Any
+    }
+}
+
+fn leap(letter: char) -> Option<(i8, i8)> {
+    let leap = match letter {
+        'W' => (1, 0),
+        'F' => (1, 1),
+        'D' => (2, 0),
+        'N' => (2, 1),
+        'A' => (2, 2),
+        'H' => (3, 0),
+        'C' => (3, 1),
+        'Z' => (3, 2),
+        'G' => (3, 3),
+        _ => return None,
+    };
+    Some(leap)
+}
+
+/// Atoms a letter stands for, and whether the letter is a rider by itself
+fn atoms_of(letter: char) -> Option<(Vec<(i8, i8)>, bool)> {
+    let atoms = match letter {
+        'K' => (vec![(1, 0), (1, 1)], false),
+        'R' => (vec![(1, 0)], true),
+        'B' => (vec![(1, 1)], true),
+        'Q' => (vec![(1, 0), (1, 1)], true),
+        _ => (vec![leap(letter)?], false),
+    };
+    Some(atoms)
+}
+
+/// Turns the direction letters before an atom into the directions they allow
+fn directions(letters: &[char], leap: (i8, i8)) -> Vec<Direction> {
+    let orthogonal = leap.1 == 0;
+    let oblique = leap.0 != leap.1 && !orthogonal;
+
+    let single = |letter: char| match letter {
+        'f' => Direction {
+            forward: Some(true),
+            ..Direction::default()
+        },
+        'b' => Direction {
+            forward: Some(false),
+            ..Direction::default()
+        },
+        'l' => Direction {
+            right: Some(false),
+            ..Direction::default()
+        },
+        'r' => Direction {
+            right: Some(true),
+            ..Direction::default()
+        },
+        'v' => Direction {
+            vertical: Some(true),
+            ..Direction::default()
+        },
+        _ => Direction {
+            vertical: Some(false),
+            ..Direction::default()
+        },
+    };
+
+    let mut directions = Vec::new();
+    let mut index = 0;
+    while index < letters.len() {
+        let first = letters[index];
+        let second = letters.get(index + 1).copied();
+        let combined = match (first, second) {
+            ('f' | 'b', Some('l' | 'r')) if !orthogonal => true,
+            ('f', Some('f')) | ('b', Some('b')) | ('f' | 'b', Some('s')) => oblique,
+            _ => false,
+        };
+
+        let mut direction = single(first);
+        if let (true, Some(second)) = (combined, second) {
+            let second = single(second);
+            direction.right = second.right;
+            direction.vertical = if second.forward.is_some() {
+                Some(true)
+            } else {
+                second.vertical
+            };
+            index += 1;
+        }
+        directions.push(direction);
+        index += 1;
+    }
+    directions
+}
+
+impl FromStr for Betza {
+    type Err = BetzaError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = notation.chars().collect();
+        let mut atoms = Vec::new();
+        let mut modifiers = Vec::new();
+
+        let mut index = 0;
+        while index < chars.len() {
+            let letter = chars[index];
+            index += 1;
+
+            if "fblrvsmcine".contains(letter) {
+                modifiers.push(letter);
+                continue;
+            }
+            let Some((leaps, mut rider)) = atoms_of(letter) else {
+                return Err(BetzaError::UnknownLetter(letter));
+            };
+
+            // Doubled letters ride, and a number limits the ride
+            if chars.get(index) == Some(&letter) {
+                rider = true;
+                index += 1;
+            }
+            let digits: String = chars[index..]
+                .iter()
+                .take_while(|digit| digit.is_ascii_digit())
+                .collect();
+            index += digits.len();
+            let range = if digits.is_empty() {
+                if rider {
+                    None
+                } else {
+                    Some(1)
+                }
+            } else {
+                match digits.parse::<u8>() {
+                    Ok(range) if range > 0 => Some(range),
+                    _ => return Err(BetzaError::Range(digits)),
+                }
+            };
+
+            let direction_letters: Vec<char> = modifiers
+                .iter()
+                .copied()
+                .filter(|letter| "fblrvs".contains(*letter))
+                .collect();
+            let capture = modifiers.contains(&'c') || modifiers.contains(&'e');
+            let mode = match (modifiers.contains(&'m'), capture) {
+                (true, false) => Mode::Move,
+                (false, true) => Mode::Capture,
+                _ => Mode::Any,
+            };
+            for leap in leaps {
+                atoms.push(Atom {
+                    leap,
+                    range,
+                    directions: directions(&direction_letters, leap),
+                    mode,
+                    initial: modifiers.contains(&'i'),
+                    lame: modifiers.contains(&'n'),
+                    en_passant: modifiers.contains(&'e'),
+                });
+            }
+            modifiers.clear();
+        }
+
+        if !modifiers.is_empty() {
+            return Err(BetzaError::DanglingModifiers(
+                modifiers.into_iter().collect(),
+            ));
+        }
+        if atoms.is_empty() {
+            return Err(BetzaError::Empty);
+        }
+
+        Ok(Betza {
+            notation: notation.to_string(),
+            atoms,
+        })
+    }
+}
+
+impl fmt::Display for Betza {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.notation)
+    }
+}
+
+impl Betza {
+    /// Every move a piece with this notation can make from the origin
+    pub fn moves(&self, board: &Board, origin: Position, color: Color, moved: bool) -> Vec<Move> {
+        let occupied: HashMap<Position, Color> = board
+            .get_pieces()
+            .map(|piece| (piece.get_position(), piece.get_color()))
+            .collect();
+        // Every player looks at the board from their own side
+        let forward = color.forward();
+        let en_passant = board.get_en_passant().zip(board.en_passant_victim(color));
+
+        let mut moves = Vec::new();
+        for atom in self.atoms.iter().filter(|atom| !atom.initial || !moved) {
+            let destinations = if atom.is_slider() {
+                self.slide(board, atom, origin, color, forward)
+            } else {
+                self.jump(board, atom, origin, &occupied, forward)
+            };
+
+            for destination in destinations {
+                let effect = match occupied.get(&destination) {
+                    Some(occupant) if *occupant == color => continue,
+                    Some(_) if atom.mode == Mode::Move => continue,
+                    Some(_) => Some(Effect::Capture),
+                    None => match en_passant {
+                        Some((square, victim)) if atom.en_passant && square == destination => {
+                            Some(Effect::EnPassant(victim))
+                        }
+                        _ if atom.mode == Mode::Capture => continue,
+                        _ => None,
+                    },
+                };
+                let mov = Move::new(origin, destination, effect);
+                if !moves.contains(&mov) {
+                    moves.push(mov);
+                }
+            }
+        }
+        moves
+    }
+
+    /// Destinations of orthogonal and diagonal riders, using the ranges of the board
+    fn slide(
+        &self,
+        board: &Board,
+        atom: &Atom,
+        origin: Position,
+        color: Color,
+        forward: (i8, i8),
+    ) -> Vec<Position> {
+        let squares = if atom.leap == (1, 0) {
+            let mut squares = board.horizontal_range(origin, atom.range, color).0;
+            squares.extend(board.vertical_range(origin, atom.range, color).0);
+            squares
+        } else {
+            board.diagonal_range(origin, atom.range, color).0
+        };
+
+        let steps = atom.steps();
+        squares
+            .into_iter()
+            .filter(|square| {
+                let dx = (square.x.0 - origin.x.0).signum();
+                let dy = (square.y.0 - origin.y.0).signum();
+                steps.contains(&from_board(forward, dx, dy))
+            })
+            .collect()
+    }
+
+    /// Destinations of leapers, and of riders that can't use the ranges of the board
+    fn jump(
+        &self,
+        board: &Board,
+        atom: &Atom,
+        origin: Position,
+        occupied: &HashMap<Position, Color>,
+        forward: (i8, i8),
+    ) -> Vec<Position> {
+        let square = |x: i8, y: i8| {
+            let (x, y) = to_board(forward, x, y);
+            Position::new(XAxis::new(origin.x.0 + x), YAxis::new(origin.y.0 + y))
+        };
+
+        let mut destinations = Vec::new();
+        for (dx, dy) in atom.steps() {
+            if atom.lame && blocked(dx, dy, |x, y| occupied.contains_key(&square(x, y))) {
+                continue;
+            }
+
+            let mut distance = 1;
+            loop {
+                let destination = square(dx * distance, dy * distance);
+                if !board.is_inside(&destination) {
+                    break;
+                }
+                destinations.push(destination);
+                if occupied.contains_key(&destination) {
+                    break;
+                }
+                if atom
+                    .range
+                    .is_some_and(|range| i16::from(distance) >= i16::from(range))
+                {
+                    break;
+                }
+                distance += 1;
+            }
+        }
+        destinations
+    }
+}
+
+/// Turns a move seen by a player, whose forward is the given direction, into a move on the board
+fn to_board((fx, fy): (i8, i8), x: i8, y: i8) -> (i8, i8) {
+    (x * fy + y * fx, y * fy - x * fx)
+}
+
+/// Turns a move on the board into the move seen by a player
+fn from_board((fx, fy): (i8, i8), x: i8, y: i8) -> (i8, i8) {
+    (x * fy - y * fx, x * fx + y * fy)
+}
+
+/// Whether a piece is standing on the way of a leap. Straight leaps go through every square in
+/// between, and the oblique ones take an orthogonal step first, like the Xiangqi horse
+fn blocked(dx: i8, dy: i8, occupied: impl Fn(i8, i8) -> bool) -> bool {
+    if dx == 0 || dy == 0 || dx.abs() == dy.abs() {
+        let length = dx.abs().max(dy.abs());
+        (1..length).any(|step| occupied(dx.signum() * step, dy.signum() * step))
+    } else {
+        occupied(dx - dx.signum(), dy - dy.signum())
+    }
+}
+
+// ================================ FairyPiece =================================
+/// A piece whose moves are given by its Betza notation, so new pieces don't need any code
+#[derive(Clone)]
+pub struct FairyPiece {
+    /// Lowercase letter that identifies the piece, like in FEN
+    letter: char,
+    betza: Arc<Betza>,
+    color: Color,
+    position: Position,
+    already_moved: bool,
+}
+
+impl FairyPiece {
+    pub fn new(letter: char, betza: Betza, color: Color, position: Position) -> Self {
+        Self {
+            letter: letter.to_ascii_lowercase(),
+            betza: Arc::new(betza),
+            color,
+            position,
+            already_moved: false,
+        }
+    }
+
+    pub fn get_betza(&self) -> &Betza {
+        &self.betza
+    }
+}
+
+impl CurrentPosition for FairyPiece {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+}
+
+impl Recognizable for FairyPiece {
+    fn get_type(&self) -> PieceType {
+        PieceType::Fairy(self.letter)
+    }
+}
+
+impl Colored for FairyPiece {
+    fn get_color(&self) -> Color {
+        self.color
+    }
+}
+
+impl Moveset for FairyPiece {
+    fn move_to(&mut self, destination: Position) {
+        self.already_moved = true;
+        self.position = destination;
+    }
+
+    fn available_positions(&self, board: &Board) -> Vec<Move> {
+        self.betza
+            .moves(board, self.position, self.color, self.already_moved)
+    }
+}
+
+impl Piece for FairyPiece {
+    fn was_moved(&self) -> bool {
+        self.already_moved
+    }
+
+    fn set_moved(&mut self, moved: bool) {
+        self.already_moved = moved;
+    }
+
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rules;
+
+    fn fairy(fen: &str, betza: &str, color: Color, x: i8, y: i8) -> (Board, FairyPiece) {
+        let position = Position::new(x.into(), y.into());
+        let piece = FairyPiece::new('x', betza.parse().unwrap(), color, position);
+        let mut pieces: Vec<_> = Board::from_fen(fen)
+            .unwrap()
+            .get_pieces()
+            .cloned()
+            .collect();
+        pieces.push(Box::new(piece.clone()));
+        (Board::with_dimensions(pieces, 8, 8), piece)
+    }
+
+    fn destinations(board: &Board, piece: &dyn Piece) -> Vec<(i8, i8)> {
+        let mut destinations: Vec<_> = piece
+            .available_positions(board)
+            .into_iter()
+            .map(|mov| (mov.destination.x.0, mov.destination.y.0))
+            .collect();
+        destinations.sort();
+        destinations
+    }
+
+    #[test]
+    fn parse_test() {
+        assert!("WfceFifmnD".parse::<Betza>().is_ok());
+        assert_eq!("".parse::<Betza>(), Err(BetzaError::Empty));
+        assert_eq!("pR".parse::<Betza>(), Err(BetzaError::UnknownLetter('p')));
+        assert_eq!(
+            "Nfm".parse::<Betza>(),
+            Err(BetzaError::DanglingModifiers("fm".to_string()))
+        );
+        assert_eq!(
+            "W0".parse::<Betza>(),
+            Err(BetzaError::Range("0".to_string()))
+        );
+        assert_eq!("BN".parse::<Betza>().unwrap().to_string(), "BN");
+    }
+
+    #[test]
+    fn standard_pieces_test() {
+        let fen = "4k3/8/2p5/8/8/8/8/4K3 w - - 0 1";
+        let standard = Board::from_fen("4k3/8/2p5/8/3Q4/8/8/4K3 w - - 0 1").unwrap();
+        let queen = standard
+            .find_pieces(Some(PieceType::Queen), None)
+            .next()
+            .unwrap();
+
+        let (board, piece) = fairy(fen, "Q", Color::White, 3, 3);
+        assert_eq!(
+            destinations(&board, &piece),
+            destinations(&standard, queen.as_ref())
+        );
+
+        let (board, piece) = fairy(fen, "RB", Color::White, 3, 3);
+        assert_eq!(
+            destinations(&board, &piece),
+            destinations(&standard, queen.as_ref())
+        );
+
+        let (board, piece) = fairy(fen, "N", Color::White, 0, 0);
+        assert_eq!(destinations(&board, &piece), vec![(1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn pawn_test() {
+        let fen = "4k3/8/8/8/8/2p5/8/4K3 w - - 0 1";
+        let (board, mut piece) = fairy(fen, "fmWfceFifmnD", Color::White, 1, 1);
+        assert_eq!(destinations(&board, &piece), vec![(1, 2), (1, 3), (2, 2)]);
+
+        piece.move_to(Position::new(1.into(), 2.into()));
+        assert_eq!(destinations(&board, &piece), vec![(1, 3)]);
+
+        // The double step can't jump over a piece
+        let (board, piece) = fairy(fen, "fmWfceFifmnD", Color::White, 2, 1);
+        assert_eq!(destinations(&board, &piece), Vec::<(i8, i8)>::new());
+
+        // Black pawns go down the board
+        let (board, piece) = fairy(fen, "fmWfceFifmnD", Color::Black, 1, 6);
+        assert_eq!(destinations(&board, &piece), vec![(1, 4), (1, 5)]);
+    }
+
+    #[test]
+    fn en_passant_test() {
+        let mut rules = Rules::default();
+        rules
+            .fairy_pieces
+            .insert('x', "fmWfceFifmnD".parse().unwrap());
+        let fen = "4k3/8/8/3pX3/8/8/8/4K3 w - d6 0 2";
+        let mut board = Board::from_fen_with_rules(fen, rules.clone()).unwrap();
+
+        let capture = board.parse_move("e5d6").unwrap();
+        let victim = Position::new(3.into(), 4.into());
+        assert_eq!(capture.effect, Some(Effect::EnPassant(victim)));
+        board.execute_move(capture);
+        assert_eq!(board.find_pieces(Some(PieceType::Pawn), None).count(), 0);
+
+        // Only right after the double step
+        let fen = "4k3/8/8/3pX3/8/8/8/4K3 w - - 0 2";
+        let board = Board::from_fen_with_rules(fen, rules).unwrap();
+        assert!(board.parse_move("e5d6").is_err());
+    }
+
+    #[test]
+    fn archbishop_test() {
+        let (board, piece) = fairy("7k/8/8/8/8/8/8/7K w - - 0 1", "BN", Color::White, 0, 0);
+        assert_eq!(
+            destinations(&board, &piece),
+            vec![
+                (1, 1),
+                (1, 2),
+                (2, 1),
+                (2, 2),
+                (3, 3),
+                (4, 4),
+                (5, 5),
+                (6, 6),
+                (7, 7)
+            ]
+        );
+
+        // The archbishop checks along the diagonal, like a bishop would
+        assert!(board.is_in_check(Color::Black));
+    }
+
+    #[test]
+    fn directions_test() {
+        let fen = "7k/8/8/8/8/8/8/7K w - - 0 1";
+
+        let (board, piece) = fairy(fen, "ffN", Color::White, 3, 3);
+        assert_eq!(destinations(&board, &piece), vec![(2, 5), (4, 5)]);
+
+        let (board, piece) = fairy(fen, "fsW", Color::White, 3, 3);
+        assert_eq!(destinations(&board, &piece), vec![(2, 3), (3, 4), (4, 3)]);
+
+        let (board, piece) = fairy(fen, "flF", Color::Black, 3, 3);
+        assert_eq!(destinations(&board, &piece), vec![(4, 2)]);
+
+        let (board, piece) = fairy(fen, "vR2", Color::White, 3, 3);
+        assert_eq!(
+            destinations(&board, &piece),
+            vec![(3, 1), (3, 2), (3, 4), (3, 5)]
+        );
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
 
 use std::collections::HashSet;
 // ================================== King =====================================
+#[derive(Clone)]
 pub struct King {
     color: Color,
     position: Position,
@@ -73,54 +74,21 @@ impl Moveset for King {
             .map(|piece| piece.get_position())
             .collect();
 
-        let castling = {
-            if self.already_moved == true {
-                return Vec::new();
-            }
-
-            //TODO: Check if there are no kings
-            let rooks: Vec<_> = board
-                .find_pieces(Some(PieceType::Rook), Some(self.color))
-                // Only get rooks that haven't been moved
-                .filter(|rook| rook.was_moved() == false)
-                .filter(|rook| {
-                    let mut range = {
-                        if self.get_position().x < rook.get_position().x {
-                            self.get_position().x.0 + 1..rook.get_position().x.0
-                        } else {
-                            rook.get_position().x.0 + 1..self.get_position().x.0
-                        }
-                    }
-                    .map(|x| Position::new(x.into(), self.get_position().y));
-
-                    !range.any(|pos| teammates.contains(&pos) || opponents.contains(&pos))
-                })
-                .map(|rook| {
-                    let distance = self.get_position().sub_x(rook.get_position().x);
-                    let normalized_distance = distance.x.0.abs();
-                    let direction: i8 = distance.x.0 / normalized_distance;
-
-                    let rook_position = rook.get_position();
-
-                    let rook_destination = self.get_position().sub_x((1 * direction).into());
-                    let king_destination = self.get_position().sub_x((2 * direction).into());
-
-                    Move::new(
-                        self.get_position(),
-                        king_destination,
-                        Some(Effect::Castling {
-                            origin: rook_position,
-                            destination: rook_destination,
-                        }),
-                    )
-                })
-                .collect();
-
-            rooks
-        }
-        .into_iter();
+        let castling = board
+            .castlings(self.color)
+            .into_iter()
+            .filter(|castling| castling.king.0 == self.get_position())
+            .map(|castling| {
+                Move::new(
+                    castling.king.0,
+                    castling.king.1,
+                    Some(Effect::Castling {
+                        origin: castling.rook.0,
+                        destination: castling.rook.1,
+                    }),
+                )
+            });
 
-        // TODO Castling
         // TODO Moves that put you in check
         // TODO King is in adjacent square.
         let possible_positions = max_range
@@ -150,4 +118,12 @@ impl Piece for King {
     fn was_moved(&self) -> bool {
         self.already_moved
     }
+
+    fn set_moved(&mut self, moved: bool) {
+        self.already_moved = moved;
+    }
+
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
 use std::collections::HashSet;
 
 // ================================= Knight ====================================
+#[derive(Clone)]
 pub struct Knight {
     color: Color,
     position: Position,
@@ -91,4 +92,8 @@ impl Moveset for Knight {
     }
 }
 
-impl Piece for Knight {}
+impl Piece for Knight {
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
@@ -1,13 +1,21 @@
+mod archbishop;
 mod bishop;
+mod chancellor;
+mod fairy;
 mod king;
 mod knight;
 mod pawn;
+mod promoted;
 mod queen;
 mod rook;
 
+pub use archbishop::Archbishop;
 pub use bishop::Bishop;
+pub use chancellor::Chancellor;
+pub use fairy::{Betza, BetzaError, FairyPiece};
 pub use king::King;
 pub use knight::Knight;
 pub use pawn::Pawn;
+pub use promoted::Promoted;
 pub use queen::Queen;
 pub use rook::Rook;
@@ -5,6 +5,7 @@ use crate::{
 
 use std::collections::HashSet;
 // ================================== Pawn =====================================
+#[derive(Clone)]
 pub struct Pawn {
     color: Color,
     position: Position,
@@ -49,19 +50,13 @@ impl Moveset for Pawn {
     }
 
     fn available_positions(&self, board: &Board) -> Vec<Move> {
-        // Black Pawns move "up" in the negative y position
-        let direction = if self.color == Color::Black {
-            -1
-        // White Pawns move "up" in the positive y position
-        } else {
-            1
-        };
-
-        let move_up = self.position.add_y(YAxis::new(direction));
-        let mut possible_move_positions = vec![move_up];
-        if self.already_moved == false {
-            let initial_possibility = self.position.add_y(YAxis::new(direction * 2));
-            possible_move_positions.push(initial_possibility);
+        // White pawns move up, black ones down, and the other players towards the opposite side
+        let (dx, dy) = self.color.forward();
+        let step = |position: Position, distance: i8| {
+            Position::new(
+                position.x + XAxis::new(dx * distance),
+                position.y + YAxis::new(dy * distance),
+            )
         };
 
         let occupied_positions: HashSet<_> = board
@@ -69,16 +64,25 @@ impl Moveset for Pawn {
             .map(|piece| piece.get_position())
             .collect();
 
-        let (bl, _, ul, _) = board.get_limits();
+        let move_up = step(self.position, 1);
+        let mut possible_move_positions = vec![move_up];
+        // Pawns start on the second rank, or on the first one in Horde. Both squares in front
+        // have to be empty
+        if !self.already_moved
+            && board.get_rules().pawn_double_step
+            && !occupied_positions.contains(&move_up)
+        {
+            let initial_possibility = step(self.position, 2);
+            possible_move_positions.push(initial_possibility);
+        };
+
         let possible_move_positions = possible_move_positions
             .into_iter()
+            .filter(|position| board.is_inside(position))
             .filter(|position| !occupied_positions.contains(position))
             .map(|position| {
-                let color = self.get_color();
                 // If it can move to the last lane, then it can get promoted
-                let effect = if (position.y == ul.0.y && color == Color::White)
-                    || (position.y == bl.0.y && color == Color::Black)
-                {
+                let effect = if board.is_promotion_square(self.color, position) {
                     Some(Effect::Promotion(None))
                 } else {
                     None
@@ -91,19 +95,32 @@ impl Moveset for Pawn {
             .filter(|piece| piece.get_color() != self.color)
             .map(|piece| piece.get_position())
             .collect();
+        // A pawn that just advanced two squares can be captured on the square it skipped
+        let en_passant = board
+            .get_en_passant()
+            .zip(board.en_passant_victim(self.color));
 
         let possible_attack_positions = vec![
-            Position::new(self.get_position().x - XAxis::new(1), move_up.y),
-            Position::new(self.get_position().x + XAxis::new(1), move_up.y),
+            // Sideways from the square in front
+            Position::new(move_up.x + XAxis::new(dy), move_up.y + YAxis::new(dx)),
+            Position::new(move_up.x - XAxis::new(dy), move_up.y - YAxis::new(dx)),
         ]
         .into_iter()
         // Remove moves outside the board
         .filter(|pos| board.is_inside(pos))
         // Remove attack moves that aren't attacking
-        // TODO: Add en passant
-        // TODO: Add initial double move
-        .filter(|pos| enemy_possition.contains(pos))
-        .map(|pos| Move::new(self.get_position(), pos, Some(Effect::Capture)));
+        .filter(|pos| {
+            enemy_possition.contains(pos) || en_passant.is_some_and(|(square, _)| square == *pos)
+        })
+        .map(|pos| {
+            // Capturing into the last lane also promotes, the capture is implied
+            let effect = match en_passant {
+                Some((square, victim)) if square == pos => Effect::EnPassant(victim),
+                _ if board.is_promotion_square(self.color, pos) => Effect::Promotion(None),
+                _ => Effect::Capture,
+            };
+            Move::new(self.get_position(), pos, Some(effect))
+        });
 
         let possible_move_positions: Vec<Move> = possible_move_positions
             .chain(possible_attack_positions)
@@ -117,6 +134,14 @@ impl Piece for Pawn {
     fn was_moved(&self) -> bool {
         self.already_moved
     }
+
+    fn set_moved(&mut self, moved: bool) {
+        self.already_moved = moved;
+    }
+
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
 }
 
 // impl Promotable for Pawn {
@@ -0,0 +1,64 @@
+use crate::{
+    piece::Piece, Board, Color, Colored, CurrentPosition, Move, Moveset, PieceType, Position,
+    Recognizable,
+};
+
+// ================================ Promoted ==================================
+/// A piece that started the game as a pawn. It moves like the piece it was promoted to, but
+/// some variants, like Crazyhouse, turn it back into a pawn once it's captured
+#[derive(Clone)]
+pub struct Promoted {
+    piece: Box<dyn Piece>,
+}
+
+impl Promoted {
+    pub fn new(piece: Box<dyn Piece>) -> Self {
+        Self { piece }
+    }
+}
+
+impl CurrentPosition for Promoted {
+    fn get_position(&self) -> Position {
+        self.piece.get_position()
+    }
+}
+
+impl Recognizable for Promoted {
+    fn get_type(&self) -> PieceType {
+        self.piece.get_type()
+    }
+}
+
+impl Colored for Promoted {
+    fn get_color(&self) -> Color {
+        self.piece.get_color()
+    }
+}
+
+impl Moveset for Promoted {
+    fn move_to(&mut self, destination: Position) {
+        self.piece.move_to(destination);
+    }
+
+    fn available_positions(&self, board: &Board) -> Vec<Move> {
+        self.piece.available_positions(board)
+    }
+}
+
+impl Piece for Promoted {
+    fn was_moved(&self) -> bool {
+        self.piece.was_moved()
+    }
+
+    fn set_moved(&mut self, moved: bool) {
+        self.piece.set_moved(moved);
+    }
+
+    fn was_promoted(&self) -> bool {
+        true
+    }
+
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
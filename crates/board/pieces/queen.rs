@@ -6,6 +6,7 @@ use crate::{
 use std::collections::HashSet;
 
 // ================================= Queen ====================================
+#[derive(Clone)]
 pub struct Queen {
     color: Color,
     position: Position,
@@ -86,4 +87,8 @@ impl Moveset for Queen {
     }
 }
 
-impl Piece for Queen {}
+impl Piece for Queen {
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
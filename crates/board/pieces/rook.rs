@@ -5,6 +5,7 @@ use crate::{
 use std::collections::HashSet;
 
 // ================================== Rook =====================================
+#[derive(Clone)]
 pub struct Rook {
     color: Color,
     position: Position,
@@ -34,74 +35,26 @@ impl Moveset for Rook {
         // This is the maximum possible range from the Rook's position
         let plus_range = PlusRange::from(horizontal_axis, vertical_axis);
 
-        let (teammates, opponents): (Vec<_>, Vec<_>) = board
+        let opponents: HashSet<_> = board
             .get_pieces()
-            .partition(|piece| piece.get_color() == self.color);
-        let teammates: HashSet<_> = teammates
-            .into_iter()
+            .filter(|piece| piece.get_color() != self.color)
             .map(|piece| piece.get_position())
             .collect();
-        let opponents: HashSet<_> = opponents
-            .into_iter()
-            .map(|piece| piece.get_position())
-            .collect();
-
-        let castling = 'castle: {
-            if self.already_moved == true {
-                break 'castle Vec::new();
-            }
-
-            //TODO: Tidy this up
-            let king: Vec<_> = board
-                .find_pieces(Some(PieceType::King), Some(self.color))
-                .collect();
-            if king.len() == 0 {
-                break 'castle Vec::new();
-            } else if king.len() > 1 {
-                panic!("More than one king in the board");
-            };
-            let king = king.into_iter().nth(0).unwrap();
-
-            if king.was_moved() == true {
-                break 'castle Vec::new();
-            }
-            let distance = king.get_position().sub_x(self.get_position().x);
-
-            let normalized_distance = distance.x.0.abs();
-            let direction: i8 = distance.x.0 / normalized_distance;
-
-            //TODO: Clean this shit up
-            // Checks if there are pieces in between the rook and the king
-            {
-                let mut temp;
-                temp = self.get_position();
-                while temp.x != direction.into() || temp == king.get_position() {
-                    temp.x += direction.into();
-                    if temp != king.get_position() && teammates.contains(&temp)
-                        || opponents.contains(&temp)
-                    {
-                        break 'castle Vec::new();
-                    }
-                }
-            };
 
-            let king_position = self.get_position().add_x(distance.x);
-
-            let rook_destination = king_position.sub_x((1 * direction).into());
-            let king_destination = king_position.sub_x((2 * direction).into());
-
-            let rook_move = Move::new(
-                self.get_position(),
-                rook_destination,
-                Some(Effect::Castling {
-                    origin: king.get_position(),
-                    destination: king_destination,
-                }),
-            );
-
-            vec![rook_move]
-        }
-        .into_iter();
+        let castling = board
+            .castlings(self.color)
+            .into_iter()
+            .filter(|castling| castling.rook.0 == self.get_position())
+            .map(|castling| {
+                Move::new(
+                    castling.rook.0,
+                    castling.rook.1,
+                    Some(Effect::Castling {
+                        origin: castling.king.0,
+                        destination: castling.king.1,
+                    }),
+                )
+            });
 
         let possible_positions = plus_range
             .0
@@ -148,4 +101,12 @@ impl Piece for Rook {
     fn was_moved(&self) -> bool {
         self.already_moved
     }
+
+    fn set_moved(&mut self, moved: bool) {
+        self.already_moved = moved;
+    }
+
+    fn box_clone(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
 }
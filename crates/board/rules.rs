@@ -0,0 +1,265 @@
+use crate::{Betza, ChoiceOfPromotablePiece, STARTING_POSITION};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Variants that change how the game is won, or which moves are legal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Captured pieces can be dropped back on the board by the capturer
+    Crazyhouse,
+    /// Played by two teams on two boards. Captured pieces are given to the partner of the
+    /// capturer, who can drop them on the other board
+    Bughouse,
+    /// Captures explode, removing every piece around them except for pawns. Kings can't capture,
+    /// and blowing up the enemy king wins the game
+    Atomic,
+    /// Captures are compulsory and the king is an ordinary piece. Losing every piece, or having
+    /// no moves left, wins the game
+    Antichess,
+    /// Giving check three times wins the game
+    ThreeCheck,
+    /// Bringing the king to one of the four squares in the center wins the game
+    KingOfTheHill,
+    /// Both kings race to the last rank, and no piece can give check
+    RacingKings,
+    /// White has 36 pawns and no king, and loses once every piece is captured
+    Horde,
+    /// Players only see the squares their pieces can move to. There's no check, the game ends
+    /// when a king is captured
+    FogOfWar,
+    /// 10x8 board with an archbishop and a chancellor, which pawns can be promoted to as well
+    Capablanca,
+    /// 10x10 board with an archbishop and a chancellor, and no castling. Pawns start on the
+    /// third rank. Promotion is simplified: pawns are promoted on the last rank to any piece, as
+    /// in Capablanca chess. Real Grand chess lets them promote on the eighth and ninth ranks too,
+    /// and only to a piece the player has lost
+    Grand,
+    /// Red, blue, yellow and green play on a 14x14 board without its 3x3 corners. Players are
+    /// out once they are checkmated, stalemated or lose their king, and points decide the winner
+    FourPlayer,
+}
+
+impl Variant {
+    /// Variants that can be played on a board of their own. Bughouse is left out, its boards
+    /// are only played inside a Bughouse
+    pub const ALL: [Variant; 12] = [
+        Variant::Standard,
+        Variant::Crazyhouse,
+        Variant::Atomic,
+        Variant::Antichess,
+        Variant::ThreeCheck,
+        Variant::KingOfTheHill,
+        Variant::RacingKings,
+        Variant::Horde,
+        Variant::FogOfWar,
+        Variant::Capablanca,
+        Variant::Grand,
+        Variant::FourPlayer,
+    ];
+
+    pub fn rules(self) -> Rules {
+        match self {
+            Variant::Standard => Rules::default(),
+            Variant::Crazyhouse => Rules::crazyhouse(),
+            Variant::Bughouse => Rules {
+                variant: self,
+                ..Rules::crazyhouse()
+            },
+            Variant::Atomic => Rules::atomic(),
+            Variant::Antichess => Rules::antichess(),
+            Variant::RacingKings => Rules {
+                castling: false,
+                variant: self,
+                ..Rules::default()
+            },
+            Variant::Capablanca => Rules {
+                promotions: Rules::capablanca_promotions(),
+                variant: self,
+                ..Rules::default()
+            },
+            // Promotes like Capablanca chess, see the variant
+            Variant::Grand => Rules {
+                castling: false,
+                promotions: Rules::capablanca_promotions(),
+                variant: self,
+                ..Rules::default()
+            },
+            // Pawns are promoted in the middle of the board
+            Variant::FourPlayer => Rules {
+                castling: false,
+                promotion_rank: Some(8),
+                variant: self,
+                ..Rules::default()
+            },
+            Variant::ThreeCheck | Variant::KingOfTheHill | Variant::Horde | Variant::FogOfWar => {
+                Rules {
+                    variant: self,
+                    ..Rules::default()
+                }
+            }
+        }
+    }
+
+    /// Starting position in FEN. Four-player chess has none, since FEN only knows about white
+    /// and black
+    pub fn starting_position(self) -> Option<&'static str> {
+        let fen = match self {
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            Variant::Horde => {
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+            }
+            Variant::Capablanca => {
+                "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+            }
+            Variant::Grand => {
+                "r8r/1nbqkcabn1/pppppppppp/10/10/10/10/PPPPPPPPPP/1NBQKCABN1/R8R w - - 0 1"
+            }
+            Variant::FourPlayer => return None,
+            _ => STARTING_POSITION,
+        };
+        Some(fen)
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Variant::Standard => "standard",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Bughouse => "bughouse",
+            Variant::Atomic => "atomic",
+            Variant::Antichess => "antichess",
+            Variant::ThreeCheck => "threecheck",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::RacingKings => "racingkings",
+            Variant::Horde => "horde",
+            Variant::FogOfWar => "fogofwar",
+            Variant::Capablanca => "capablanca",
+            Variant::Grand => "grand",
+            Variant::FourPlayer => "fourplayer",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVariantError(String);
+
+impl fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown variant: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseVariantError {}
+
+impl FromStr for Variant {
+    type Err = ParseVariantError;
+
+    /// Takes the name of the variant, as written by Display, or one of its common aliases
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        match name.as_str() {
+            "losing" => return Ok(Variant::Antichess),
+            "3check" | "three-check" => return Ok(Variant::ThreeCheck),
+            "koth" | "king-of-the-hill" => return Ok(Variant::KingOfTheHill),
+            "fog" | "dark" | "fog-of-war" => return Ok(Variant::FogOfWar),
+            "4p" | "four-player" => return Ok(Variant::FourPlayer),
+            _ => (),
+        }
+        Variant::ALL
+            .into_iter()
+            .find(|variant| variant.to_string() == name)
+            .ok_or_else(|| ParseVariantError(s.to_string()))
+    }
+}
+
+/// Rules that change between chess variants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Kings and rooks that haven't moved can castle
+    pub castling: bool,
+    /// Pawns that haven't moved can advance two squares
+    pub pawn_double_step: bool,
+    /// Pieces a pawn can be promoted to
+    pub promotions: Vec<ChoiceOfPromotablePiece>,
+    /// Rank where pawns are promoted, counted from the side of each player. None means the
+    /// last one
+    pub promotion_rank: Option<u8>,
+    /// Captured pieces go to the pocket of the capturer, and can be dropped back on the board
+    pub drops: bool,
+    pub variant: Variant,
+    /// How the fairy pieces of the variant move, by the lowercase letter they use in FEN
+    pub fairy_pieces: BTreeMap<char, Betza>,
+}
+
+impl Rules {
+    /// Rules for boards too small for castling and double steps
+    pub fn minichess() -> Self {
+        Rules {
+            castling: false,
+            pawn_double_step: false,
+            ..Rules::default()
+        }
+    }
+
+    pub fn crazyhouse() -> Self {
+        Rules {
+            drops: true,
+            variant: Variant::Crazyhouse,
+            ..Rules::default()
+        }
+    }
+
+    pub fn atomic() -> Self {
+        Rules {
+            variant: Variant::Atomic,
+            ..Rules::default()
+        }
+    }
+
+    /// The standard promotions, plus the archbishop and the chancellor
+    fn capablanca_promotions() -> Vec<ChoiceOfPromotablePiece> {
+        let mut promotions = Rules::default().promotions;
+        promotions.extend([
+            ChoiceOfPromotablePiece::Archbishop,
+            ChoiceOfPromotablePiece::Chancellor,
+        ]);
+        promotions
+    }
+
+    /// There's no castling, and pawns can be promoted to kings as well
+    pub fn antichess() -> Self {
+        let mut promotions = Rules::default().promotions;
+        promotions.push(ChoiceOfPromotablePiece::King);
+        Rules {
+            castling: false,
+            promotions,
+            variant: Variant::Antichess,
+            ..Rules::default()
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            castling: true,
+            pawn_double_step: true,
+            promotions: vec![
+                ChoiceOfPromotablePiece::Queen,
+                ChoiceOfPromotablePiece::Rook,
+                ChoiceOfPromotablePiece::Bishop,
+                ChoiceOfPromotablePiece::Knight,
+            ],
+            promotion_rank: None,
+            drops: false,
+            variant: Variant::Standard,
+            fairy_pieces: BTreeMap::new(),
+        }
+    }
+}
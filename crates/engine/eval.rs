@@ -0,0 +1,465 @@
+use board::{Board, Color, Move, PieceType, Position};
+
+use std::fmt;
+
+/// Value of a piece in centipawns. Kings are never traded, so they aren't worth anything
+pub fn piece_value(piece: PieceType) -> i32 {
+    match piece {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Archbishop => 800,
+        PieceType::Chancellor => 850,
+        PieceType::Queen => 900,
+        // Fairy pieces can move in any way, so they are valued like a minor piece
+        PieceType::Fairy(_) => 300,
+        PieceType::King => 0,
+    }
+}
+
+/// Phase of the game with every piece still on the board. Pawns and kings don't count
+const OPENING_PHASE: i32 = 24;
+
+/// How much a piece counts towards the middlegame
+fn phase_weight(piece: PieceType) -> i32 {
+    match piece {
+        PieceType::Knight | PieceType::Bishop | PieceType::Fairy(_) => 1,
+        PieceType::Rook => 2,
+        PieceType::Archbishop | PieceType::Chancellor => 3,
+        PieceType::Queen => 4,
+        PieceType::Pawn | PieceType::King => 0,
+    }
+}
+
+// Piece-square tables, as seen by white with the eighth rank at the top. Black, and the other
+// players, look them up from their own side of the board
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Middlegame and endgame tables of the piece. Pieces that combine others use the table of the
+/// long range one
+fn piece_square_tables(piece: PieceType) -> (&'static [i32; 64], &'static [i32; 64]) {
+    match piece {
+        PieceType::Pawn => (&PAWN_MG, &PAWN_EG),
+        PieceType::Knight | PieceType::Fairy(_) => (&KNIGHT, &KNIGHT),
+        PieceType::Bishop | PieceType::Archbishop => (&BISHOP, &BISHOP),
+        PieceType::Rook | PieceType::Chancellor => (&ROOK, &ROOK),
+        PieceType::Queen => (&QUEEN, &QUEEN),
+        PieceType::King => (&KING_MG, &KING_EG),
+    }
+}
+
+/// Middlegame and endgame bonus for each square a piece can move to
+fn mobility_weight(piece: PieceType) -> (i32, i32) {
+    match piece {
+        PieceType::Knight => (4, 4),
+        PieceType::Bishop => (5, 5),
+        PieceType::Rook => (2, 4),
+        PieceType::Queen => (1, 2),
+        PieceType::Archbishop | PieceType::Chancellor | PieceType::Fairy(_) => (3, 3),
+        PieceType::Pawn | PieceType::King => (0, 0),
+    }
+}
+
+/// Middlegame bonus for each pawn right in front of its king
+const PAWN_SHIELD: i32 = 10;
+/// Middlegame penalty for each move of the opponent into the squares around the king
+const KING_ZONE_ATTACK: i32 = 8;
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-10, -10);
+/// Bonus of a passed pawn by how far it went, counted from its own side of the board
+const PASSED_PAWN_MG: [i32; 8] = [0, 5, 10, 15, 25, 40, 60, 0];
+const PASSED_PAWN_EG: [i32; 8] = [0, 10, 20, 35, 55, 80, 110, 0];
+
+/// Middlegame and endgame score of a term, blended by the phase of the game at the end
+#[derive(Debug, Clone, Copy, Default)]
+struct Tapered {
+    mg: i32,
+    eg: i32,
+}
+
+impl Tapered {
+    fn add(&mut self, sign: i32, (mg, eg): (i32, i32)) {
+        self.mg += sign * mg;
+        self.eg += sign * eg;
+    }
+
+    fn blend(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (OPENING_PHASE - phase)) / OPENING_PHASE
+    }
+}
+
+/// Every term of the evaluation, in centipawns from the side of the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Evaluation {
+    pub material: i32,
+    pub piece_squares: i32,
+    pub mobility: i32,
+    pub king_safety: i32,
+    pub pawn_structure: i32,
+    /// From OPENING_PHASE with every piece on the board, down to 0 with only pawns and kings
+    pub phase: i32,
+}
+
+impl Evaluation {
+    pub fn total(&self) -> i32 {
+        self.material + self.piece_squares + self.mobility + self.king_safety + self.pawn_structure
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "material       {:>6}", self.material)?;
+        writeln!(f, "piece squares  {:>6}", self.piece_squares)?;
+        writeln!(f, "mobility       {:>6}", self.mobility)?;
+        writeln!(f, "king safety    {:>6}", self.king_safety)?;
+        writeln!(f, "pawn structure {:>6}", self.pawn_structure)?;
+        writeln!(f, "phase          {:>6}", self.phase)?;
+        write!(f, "total          {:>6}", self.total())
+    }
+}
+
+/// Score of the position in centipawns, from the side of the player to move
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_terms(board).total()
+}
+
+/// Like evaluate, but with each term on its own
+pub fn evaluate_terms(board: &Board) -> Evaluation {
+    let turn = board.get_turn();
+    let sign = |color: Color| if color == turn { 1 } else { -1 };
+
+    let pieces: Vec<_> = board
+        .get_pieces()
+        .map(|piece| {
+            let moves = piece.available_positions(board);
+            (
+                piece.get_type(),
+                piece.get_color(),
+                piece.get_position(),
+                moves,
+            )
+        })
+        .collect();
+
+    let phase = pieces
+        .iter()
+        .map(|(piece, ..)| phase_weight(*piece))
+        .sum::<i32>()
+        .min(OPENING_PHASE);
+
+    let mut material = 0;
+    let mut piece_squares = Tapered::default();
+    let mut mobility = Tapered::default();
+    for (piece, color, position, moves) in &pieces {
+        let sign = sign(*color);
+        material += sign * piece_value(*piece);
+
+        let (mg, eg) = piece_square_tables(*piece);
+        let index = table_index(board, *color, *position);
+        piece_squares.add(sign, (mg[index], eg[index]));
+
+        let (mg, eg) = mobility_weight(*piece);
+        let count = moves.len() as i32;
+        mobility.add(sign, (mg * count, eg * count));
+    }
+
+    let mut king_safety = Tapered::default();
+    let mut pawn_structure = Tapered::default();
+    for color in colors(board) {
+        let sign = sign(color);
+        king_safety.add(sign, (king_safety_of(board, &pieces, color), 0));
+        pawn_structure.add(sign, pawn_structure_of(board, &pieces, color));
+    }
+
+    Evaluation {
+        material,
+        piece_squares: piece_squares.blend(phase),
+        mobility: mobility.blend(phase),
+        king_safety: king_safety.blend(phase),
+        pawn_structure: pawn_structure.blend(phase),
+        phase,
+    }
+}
+
+/// Colors with pieces on the board
+fn colors(board: &Board) -> Vec<Color> {
+    let mut colors: Vec<_> = board.get_pieces().map(|piece| piece.get_color()).collect();
+    colors.sort();
+    colors.dedup();
+    colors
+}
+
+/// Column of the square as seen by the player, along their back rank
+fn relative_file(color: Color, position: Position) -> i8 {
+    match color.forward() {
+        (0, _) => position.x.0,
+        _ => position.y.0,
+    }
+}
+
+/// Squares from the back rank of the player to the opposite side, and along the back rank
+fn board_lengths(board: &Board, color: Color) -> (i32, i32) {
+    let (width, height) = board.get_dimensions();
+    match color.forward() {
+        (0, _) => (i32::from(height), i32::from(width)),
+        _ => (i32::from(width), i32::from(height)),
+    }
+}
+
+/// Scales the rank to the eight ranks of a standard board
+fn scaled_rank(board: &Board, color: Color, rank: i8) -> usize {
+    let (ranks, _) = board_lengths(board, color);
+    (i32::from(rank) * 8 / ranks).clamp(0, 7) as usize
+}
+
+/// Square of the piece-square tables the piece is on. Boards of other sizes are scaled to 8x8
+fn table_index(board: &Board, color: Color, position: Position) -> usize {
+    let (_, files) = board_lengths(board, color);
+    let rank = scaled_rank(board, color, board.relative_rank(color, position));
+    let file = (i32::from(relative_file(color, position)) * 8 / files).clamp(0, 7) as usize;
+    (7 - rank) * 8 + file
+}
+
+/// Pawns in front of the king are a bonus, and every move of the opponents into the squares
+/// around it is a penalty. It only matters in the middlegame
+fn king_safety_of(
+    board: &Board,
+    pieces: &[(PieceType, Color, Position, Vec<Move>)],
+    color: Color,
+) -> i32 {
+    let Some(king) = pieces
+        .iter()
+        .find(|(piece, owner, ..)| *piece == PieceType::King && *owner == color)
+        .map(|(_, _, position, _)| *position)
+    else {
+        return 0;
+    };
+    let king_rank = board.relative_rank(color, king);
+    let king_file = relative_file(color, king);
+
+    let shield = pieces
+        .iter()
+        .filter(|(piece, owner, ..)| *piece == PieceType::Pawn && *owner == color)
+        .filter(|(_, _, position, _)| {
+            let ahead = board.relative_rank(color, *position) - king_rank;
+            (1..=2).contains(&ahead) && (relative_file(color, *position) - king_file).abs() <= 1
+        })
+        .count() as i32;
+
+    let attacks = pieces
+        .iter()
+        .filter(|(_, owner, ..)| *owner != color)
+        .flat_map(|(.., moves)| moves)
+        .filter(|mov| {
+            let dx = (mov.destination.x.0 - king.x.0).abs();
+            let dy = (mov.destination.y.0 - king.y.0).abs();
+            dx <= 1 && dy <= 1
+        })
+        .count() as i32;
+
+    PAWN_SHIELD * shield - KING_ZONE_ATTACK * attacks
+}
+
+/// Doubled and isolated pawns are a penalty, passed pawns a bonus that grows as they advance.
+/// Only the pawns of the opposite player can stop a pawn from being passed
+fn pawn_structure_of(
+    board: &Board,
+    pieces: &[(PieceType, Color, Position, Vec<Move>)],
+    color: Color,
+) -> (i32, i32) {
+    let pawns = |owner: Color| {
+        pieces
+            .iter()
+            .filter(move |(piece, pawn_owner, ..)| {
+                *piece == PieceType::Pawn && *pawn_owner == owner
+            })
+            .map(move |(_, _, position, _)| {
+                (
+                    relative_file(color, *position),
+                    board.relative_rank(color, *position),
+                )
+            })
+    };
+    let own: Vec<_> = pawns(color).collect();
+    let enemies: Vec<_> = pawns(!color).collect();
+
+    let mut score = Tapered::default();
+    for (file, rank) in &own {
+        let on_file = |file: i8| own.iter().any(|(other, _)| *other == file);
+        // Every pawn but the most advanced one of the file is doubled
+        if own
+            .iter()
+            .any(|(other, other_rank)| other == file && other_rank > rank)
+        {
+            score.add(1, DOUBLED_PAWN);
+        }
+        if !on_file(file - 1) && !on_file(file + 1) {
+            score.add(1, ISOLATED_PAWN);
+        }
+        let blocked = enemies
+            .iter()
+            .any(|(enemy_file, enemy_rank)| (enemy_file - file).abs() <= 1 && enemy_rank > rank);
+        if !blocked {
+            let rank = scaled_rank(board, color, *rank);
+            score.add(1, (PASSED_PAWN_MG[rank], PASSED_PAWN_EG[rank]));
+        }
+    }
+    (score.mg, score.eg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(fen: &str) -> Evaluation {
+        evaluate_terms(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn starting_position_test() {
+        let evaluation = evaluate_terms(&Board::default());
+        assert_eq!(
+            evaluation,
+            Evaluation {
+                phase: OPENING_PHASE,
+                ..Evaluation::default()
+            }
+        );
+    }
+
+    #[test]
+    fn side_to_move_test() {
+        // Same position with the colors swapped and the board flipped
+        let fen = "4k3/pp6/8/8/3N4/8/1PP5/4K3 w - - 0 1";
+        let white = terms(fen);
+        let black = terms("4k3/1pp5/8/3n4/8/8/PP6/4K3 b - - 0 1");
+        assert_eq!(white, black);
+        assert!(white.material > 0);
+        assert_eq!(white.total(), evaluate(&Board::from_fen(fen).unwrap()));
+
+        let other_side = terms("4k3/pp6/8/8/3N4/8/1PP5/4K3 b - - 0 1");
+        assert_eq!(other_side.total(), -white.total());
+    }
+
+    #[test]
+    fn centralization_test() {
+        let center = terms("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+        let corner = terms("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+        assert!(center.piece_squares > corner.piece_squares);
+        assert!(center.mobility > corner.mobility);
+    }
+
+    #[test]
+    fn pawn_structure_test() {
+        // Doubled and isolated pawns against a healthy pair
+        let weak = terms("4k3/8/8/8/8/2P5/2P5/4K3 w - - 0 1");
+        let healthy = terms("4k3/8/8/8/8/8/2PP4/4K3 w - - 0 1");
+        assert!(weak.pawn_structure < healthy.pawn_structure);
+
+        // A passed pawn is worth more the further it went
+        let far = terms("4k3/8/1P6/8/8/8/8/4K3 w - - 0 1");
+        let near = terms("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1");
+        assert!(far.pawn_structure > near.pawn_structure);
+        let blocked = terms("4k3/p7/1P6/8/8/8/8/4K3 w - - 0 1");
+        assert!(blocked.pawn_structure < far.pawn_structure);
+    }
+
+    #[test]
+    fn king_safety_test() {
+        // Enough pieces are left for it to matter, it's only a middlegame term
+        let sheltered = terms("r2qk2r/8/8/8/8/8/5PPP/R2Q1RK1 w - - 0 1");
+        assert!(sheltered.king_safety > 0);
+        let exposed = terms("r2qk2r/8/8/8/5PPP/8/8/R2Q1RK1 w - - 0 1");
+        assert!(exposed.king_safety < sheltered.king_safety);
+        let attacked = terms("r2qk2r/8/8/8/8/7n/5PPP/R2Q1RK1 w - - 0 1");
+        assert!(attacked.king_safety < sheltered.king_safety);
+    }
+}
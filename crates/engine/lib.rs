@@ -0,0 +1,12 @@
+mod eval;
+mod ordering;
+mod search;
+mod table;
+mod zobrist;
+
+pub use eval::{evaluate, evaluate_terms, piece_value, Evaluation};
+pub use search::{
+    is_mate_score, search, search_with_report, search_with_table, Limits, SearchResult, MATE,
+};
+pub use table::{Bound, Entry, TableMove, TranspositionTable};
+pub use zobrist::position_hash;
@@ -0,0 +1,120 @@
+use crate::eval::piece_value;
+
+use board::{Board, ChoiceOfPromotablePiece, Effect, Move, PieceType, Position};
+
+use std::collections::HashMap;
+
+/// Quiet moves that caused a cutoff are remembered for each ply
+const KILLERS: usize = 2;
+
+const PV_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const PROMOTION_SCORE: i32 = 90_000;
+const KILLER_SCORE: i32 = 80_000;
+
+/// Piece captured by the move, if any. Promotions can capture as well
+pub(crate) fn captured_piece(board: &Board, mov: Move) -> Option<PieceType> {
+    match mov.effect {
+        Some(Effect::Capture) | Some(Effect::Promotion(_)) => board
+            .get_pieces()
+            .find(|piece| piece.get_position() == mov.destination)
+            .filter(|piece| piece.get_color() != board.get_turn())
+            .map(|piece| piece.get_type()),
+        Some(Effect::EnPassant(_)) => Some(PieceType::Pawn),
+        _ => None,
+    }
+}
+
+/// Captures and promotions, the moves that are still searched once the depth runs out
+pub(crate) fn is_noisy(board: &Board, mov: Move) -> bool {
+    matches!(mov.effect, Some(Effect::Promotion(_))) || captured_piece(board, mov).is_some()
+}
+
+/// Value of the piece a pawn is promoted to
+fn promotion_value(choice: ChoiceOfPromotablePiece) -> i32 {
+    let piece = match choice {
+        ChoiceOfPromotablePiece::Bishop => PieceType::Bishop,
+        ChoiceOfPromotablePiece::Knight => PieceType::Knight,
+        ChoiceOfPromotablePiece::Queen => PieceType::Queen,
+        ChoiceOfPromotablePiece::Rook => PieceType::Rook,
+        ChoiceOfPromotablePiece::King => PieceType::King,
+        ChoiceOfPromotablePiece::Archbishop => PieceType::Archbishop,
+        ChoiceOfPromotablePiece::Chancellor => PieceType::Chancellor,
+        ChoiceOfPromotablePiece::Fairy(letter) => PieceType::Fairy(letter),
+    };
+    piece_value(piece)
+}
+
+/// Decides which moves are searched first. The best move found by the previous iteration goes
+/// first, then captures of the most valuable victim by the least valuable attacker, promotions,
+/// the killer moves of the ply and finally the quiet moves by how often they caused a cutoff
+pub(crate) struct MoveOrdering {
+    killers: Vec<[Option<Move>; KILLERS]>,
+    history: HashMap<(Position, Position), i32>,
+}
+
+impl MoveOrdering {
+    pub(crate) fn new() -> Self {
+        MoveOrdering {
+            killers: Vec::new(),
+            history: HashMap::new(),
+        }
+    }
+
+    fn score(&self, board: &Board, mov: Move, ply: usize, pv_move: Option<Move>) -> i32 {
+        if pv_move == Some(mov) {
+            return PV_SCORE;
+        }
+        let mut score = 0;
+        if let Some(victim) = captured_piece(board, mov) {
+            let attacker = board
+                .get_pieces()
+                .find(|piece| piece.get_position() == mov.origin)
+                .map_or(0, |piece| piece_value(piece.get_type()));
+            score += CAPTURE_SCORE + 10 * piece_value(victim) - attacker / 10;
+        }
+        if let Some(Effect::Promotion(Some(choice))) = mov.effect {
+            score += PROMOTION_SCORE + promotion_value(choice);
+        }
+        if score > 0 {
+            return score;
+        }
+
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        if let Some(index) = killers.iter().position(|killer| *killer == Some(mov)) {
+            return KILLER_SCORE - index as i32;
+        }
+        self.history
+            .get(&(mov.origin, mov.destination))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sorts the moves from the most to the least promising
+    pub(crate) fn sort(&self, board: &Board, moves: &mut [Move], ply: usize, pv: Option<Move>) {
+        moves.sort_by_cached_key(|mov| -self.score(board, *mov, ply, pv));
+    }
+
+    /// Remembers a quiet move that was too good for the opponent to allow
+    pub(crate) fn store_cutoff(&mut self, board: &Board, mov: Move, ply: usize, depth: u8) {
+        if is_noisy(board, mov) {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; KILLERS]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mov) {
+            killers[1] = killers[0];
+            killers[0] = Some(mov);
+        }
+
+        let history = self
+            .history
+            .entry((mov.origin, mov.destination))
+            .or_default();
+        // Kept below the killers so that they still go first
+        *history =
+            (*history + i32::from(depth) * i32::from(depth)).min(KILLER_SCORE - KILLERS as i32);
+    }
+}
@@ -0,0 +1,467 @@
+use crate::eval::evaluate;
+use crate::ordering::{is_noisy, MoveOrdering};
+use crate::table::{Bound, TableMove, TranspositionTable};
+use crate::zobrist::position_hash;
+
+use board::{Board, Effect, GameResult, Move, Variant};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Score of checkmating right now. Mates further away score less, so the shortest one is found
+pub const MATE: i32 = 100_000;
+const INFINITY: i32 = MATE + 1;
+/// The search never goes deeper than this, quiescence included
+const MAX_PLY: usize = 128;
+const MAX_DEPTH: u8 = 64;
+/// Size of the transposition table of a search that doesn't get one
+const DEFAULT_TABLE_MB: usize = 16;
+
+/// When to stop searching. The search stops at whichever limit comes first, and without any it
+/// goes as deep as it can
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    /// Setting it from another thread stops the search, as if a limit was reached
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Limits {
+    pub fn depth(depth: u8) -> Self {
+        Limits {
+            depth: Some(depth),
+            ..Limits::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Limits {
+            nodes: Some(nodes),
+            ..Limits::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Limits {
+            time: Some(time),
+            ..Limits::default()
+        }
+    }
+}
+
+/// Outcome of a search, from the last depth that was searched completely
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// None when the player to move has no legal moves
+    pub best_move: Option<Move>,
+    /// In centipawns, from the side of the player to move. Mates are close to MATE
+    pub score: i32,
+    /// The moves both players are expected to play, starting with the best move
+    pub pv: Vec<Move>,
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Looks for the best move of the player whose turn it is, with iterative deepening negamax
+/// and alpha-beta pruning
+pub fn search(board: &Board, limits: Limits) -> SearchResult {
+    let table = TranspositionTable::new(DEFAULT_TABLE_MB);
+    search_with_table(board, limits, &table)
+}
+
+/// Like search, but it remembers the positions it searched in the given table. Keeping the
+/// table between the moves of a game saves work
+pub fn search_with_table(
+    board: &Board,
+    limits: Limits,
+    table: &TranspositionTable,
+) -> SearchResult {
+    search_with_report(board, limits, table, |_| ())
+}
+
+/// Like search_with_table, but the result of every depth is given to report as soon as that
+/// depth is searched completely
+pub fn search_with_report(
+    board: &Board,
+    limits: Limits,
+    table: &TranspositionTable,
+    mut report: impl FnMut(&SearchResult),
+) -> SearchResult {
+    table.new_search();
+    let mut searcher = Searcher {
+        table,
+        limits: limits.clone(),
+        started: Instant::now(),
+        nodes: 0,
+        stopped: false,
+        ordering: MoveOrdering::new(),
+        previous_pv: Vec::new(),
+    };
+
+    let mut result = SearchResult {
+        best_move: None,
+        score: 0,
+        pv: Vec::new(),
+        depth: 0,
+        nodes: 0,
+    };
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+    for depth in 1..=max_depth {
+        let mut pv = Vec::new();
+        let score = searcher.negamax(board, depth, 0, -INFINITY, INFINITY, &mut pv);
+        // A search that was cut short can miss the best reply, but its first move still beats
+        // an older one if nothing was found yet
+        if searcher.stopped {
+            if result.best_move.is_none() && !pv.is_empty() {
+                result.best_move = pv.first().copied();
+                result.pv = pv;
+            }
+            break;
+        }
+
+        result = SearchResult {
+            best_move: pv.first().copied(),
+            score,
+            pv: pv.clone(),
+            depth,
+            nodes: searcher.nodes,
+        };
+        searcher.previous_pv = pv;
+        report(&result);
+        // No point in searching deeper once a forced mate is found
+        if result.best_move.is_none() || is_mate_score(score) {
+            break;
+        }
+    }
+    // Stopped before the first depth was done, any legal move is better than none
+    if searcher.stopped && result.best_move.is_none() {
+        result.best_move = legal_moves(board).first().copied();
+        result.pv = result.best_move.into_iter().collect();
+    }
+    result.nodes = searcher.nodes;
+    result
+}
+
+/// Whether the score means that one of the players can force a checkmate
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE - MAX_PLY as i32
+}
+
+struct Searcher<'a> {
+    table: &'a TranspositionTable,
+    limits: Limits,
+    started: Instant,
+    nodes: u64,
+    stopped: bool,
+    ordering: MoveOrdering,
+    /// Principal variation of the previous iteration, searched first
+    previous_pv: Vec<Move>,
+}
+
+impl Searcher<'_> {
+    /// Stops the search once it's out of time or nodes
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.stopped = true;
+        }
+        // Generating the moves of a node takes far longer than reading the clock, so it's read
+        // on every node
+        if self.limits.stop.load(Ordering::Relaxed)
+            || self
+                .limits
+                .time
+                .is_some_and(|time| self.started.elapsed() >= time)
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+        // The root always searches, so that there's a best move
+        let hash = position_hash(board);
+        let entry = self.table.probe(hash);
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return score;
+            }
+        }
+
+        let mut moves = legal_moves(board);
+        if let Some(score) = terminal_score(board, &moves, ply) {
+            return score;
+        }
+
+        let table_move = entry
+            .and_then(|entry| entry.best_move)
+            .and_then(|best| moves.iter().find(|mov| best.matches(**mov)).copied());
+        let pv_move = table_move.or_else(|| self.previous_pv.get(ply).copied());
+        self.ordering.sort(board, &mut moves, ply, pv_move);
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for mov in moves {
+            let mut child = board.clone();
+            child.execute_move(mov);
+            let mut child_pv = Vec::new();
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(mov);
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mov);
+                pv.extend(child_pv);
+            }
+            if alpha >= beta {
+                self.ordering.store_cutoff(board, mov, ply, depth);
+                break;
+            }
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        let stored = score_to_table(best, ply);
+        let best_move = best_move.map(TableMove::new);
+        self.table.store(hash, depth, stored, bound, best_move);
+        best
+    }
+
+    /// Keeps searching captures and promotions, so that the position isn't evaluated in the
+    /// middle of an exchange
+    fn quiescence(&mut self, board: &Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        let moves = legal_moves(board);
+        if let Some(score) = terminal_score(board, &moves, ply) {
+            return score;
+        }
+        // The player to move can always decline the captures
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<_> = moves
+            .into_iter()
+            .filter(|mov| is_noisy(board, *mov))
+            .collect();
+        self.ordering.sort(board, &mut moves, ply, None);
+
+        for mov in moves {
+            self.nodes += 1;
+            if self.should_stop() {
+                return 0;
+            }
+            let mut child = board.clone();
+            child.execute_move(mov);
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+/// Mates are stored counting from the position, not from the root, since the same position can
+/// be reached at any ply
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if is_mate_score(score) {
+        score + score.signum() * ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if is_mate_score(score) {
+        score - score.signum() * ply as i32
+    } else {
+        score
+    }
+}
+
+/// Legal moves, with a move for each piece a pawn can be promoted to
+fn legal_moves(board: &Board) -> Vec<Move> {
+    let promotions = &board.get_rules().promotions;
+    board
+        .get_legal_moves()
+        .into_iter()
+        .flat_map(|mov| match mov.effect {
+            Some(Effect::Promotion(None)) => promotions
+                .iter()
+                .map(|choice| Move {
+                    effect: Some(Effect::Promotion(Some(*choice))),
+                    ..mov
+                })
+                .collect(),
+            _ => vec![mov],
+        })
+        .collect()
+}
+
+/// Score of a position where the game is over, from the side of the player to move
+fn terminal_score(board: &Board, moves: &[Move], ply: usize) -> Option<i32> {
+    // Variants can end the game while there are still moves left
+    let variant = board.get_rules().variant;
+    if !moves.is_empty() && variant == Variant::Standard {
+        return None;
+    }
+    let (result, _) = board.outcome()?;
+    let mate = MATE - ply as i32;
+    match result {
+        GameResult::Draw | GameResult::Ongoing => Some(0),
+        result if GameResult::win_for(board.get_turn()) == Some(result) => Some(mate),
+        _ => Some(-mate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best_move(fen: &str, depth: u8) -> SearchResult {
+        let board = Board::from_fen(fen).unwrap();
+        search(&board, Limits::depth(depth))
+    }
+
+    fn square(name: &str) -> board::Position {
+        let bytes = name.as_bytes();
+        board::Position::new(
+            ((bytes[0] - b'a') as i8).into(),
+            ((bytes[1] - b'1') as i8).into(),
+        )
+    }
+
+    #[test]
+    fn mate_in_one_test() {
+        // Back rank mate
+        let result = best_move("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 2);
+        let mov = result.best_move.unwrap();
+        assert_eq!((mov.origin, mov.destination), (square("a1"), square("a8")));
+        assert_eq!(result.score, MATE - 1);
+    }
+
+    #[test]
+    fn mated_test() {
+        let result = best_move("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -MATE);
+    }
+
+    #[test]
+    fn wins_hanging_queen_test() {
+        let result = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+        let mov = result.best_move.unwrap();
+        assert_eq!((mov.origin, mov.destination), (square("d2"), square("d5")));
+        assert!(result.score > 400);
+    }
+
+    #[test]
+    fn knight_fork_test() {
+        // The knight checks the king and attacks the rook at the same time
+        let result = best_move("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1", 3);
+        let mov = result.best_move.unwrap();
+        assert_eq!((mov.origin, mov.destination), (square("b5"), square("c7")));
+        assert_eq!(result.pv.len(), 3);
+        // Down the exchange, then a knight up
+        assert!(result.score > 200);
+    }
+
+    #[test]
+    fn mate_in_two_test() {
+        // The first rook cuts the king off, the second one mates on the back rank
+        let result = best_move("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 3);
+        let mov = result.best_move.unwrap();
+        assert_eq!(mov.destination.y, square("a7").y);
+        assert_eq!(result.score, MATE - 3);
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    fn shared_table_test() {
+        let board = Board::from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1").unwrap();
+        // Small enough for hashfull to look at every entry
+        let table = TranspositionTable::with_buckets(500);
+        let first = search_with_table(&board, Limits::depth(3), &table);
+        let second = search_with_table(&board, Limits::depth(3), &table);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+        assert!(table.hashfull() > 0);
+    }
+
+    #[test]
+    fn limits_test() {
+        let board = Board::default();
+        let result = search(&board, Limits::nodes(50));
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 51);
+
+        let result = search(&board, Limits::time(Duration::from_millis(100)));
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn stop_and_report_test() {
+        let board = Board::default();
+        let limits = Limits::default();
+        // Without limits only the flag ends the search, here once the second depth is reported
+        let stop = limits.stop.clone();
+        let table = TranspositionTable::new(1);
+        let mut depths = Vec::new();
+        let result = search_with_report(&board, limits, &table, |result| {
+            depths.push(result.depth);
+            if result.depth == 2 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 2);
+        assert_eq!(depths, [1, 2]);
+    }
+}
@@ -0,0 +1,409 @@
+use crate::zobrist::piece_code;
+
+use board::{ChoiceOfPromotablePiece, Effect, Move, Position};
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// Entries that share a bucket. A position can go in any of them
+const BUCKET_SIZE: usize = 2;
+/// Size of an entry, the key and the data
+const ENTRY_BYTES: usize = 16;
+/// Ages wrap around after this many searches
+const AGES: u8 = 64;
+/// Entries looked at to tell how full the table is
+const HASHFULL_SAMPLE: usize = 1000;
+
+/// How the stored score relates to the real score of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact
+    Exact,
+    /// The search failed high, the real score is at least this
+    Lower,
+    /// The search failed low, the real score is at most this
+    Upper,
+}
+
+/// The best move of a position, packed to fit in an entry. It's matched against the legal moves
+/// of the position to get the full Move back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableMove {
+    origin: (u8, u8),
+    destination: (u8, u8),
+    /// Piece a pawn is promoted to, or the piece that's dropped
+    tag: u8,
+}
+
+impl TableMove {
+    pub fn new(mov: Move) -> Self {
+        let square = |position: Position| (position.x.0 as u8, position.y.0 as u8);
+        TableMove {
+            origin: square(mov.origin),
+            destination: square(mov.destination),
+            tag: move_tag(mov),
+        }
+    }
+
+    /// Whether this is the packed version of the move
+    pub fn matches(&self, mov: Move) -> bool {
+        *self == TableMove::new(mov)
+    }
+
+    fn pack(self) -> u64 {
+        u64::from(self.origin.0)
+            | u64::from(self.origin.1) << 5
+            | u64::from(self.destination.0) << 10
+            | u64::from(self.destination.1) << 15
+            | u64::from(self.tag) << 20
+    }
+
+    fn unpack(bits: u64) -> Self {
+        let field = |shift: u32, size: u32| ((bits >> shift) & ((1 << size) - 1)) as u8;
+        TableMove {
+            origin: (field(0, 5), field(5, 5)),
+            destination: (field(10, 5), field(15, 5)),
+            tag: field(20, 6),
+        }
+    }
+}
+
+/// Tells apart the moves that share their squares: the different promotions, and drops
+fn move_tag(mov: Move) -> u8 {
+    match mov.effect {
+        Some(Effect::Promotion(Some(choice))) => match choice {
+            ChoiceOfPromotablePiece::Bishop => 1,
+            ChoiceOfPromotablePiece::Knight => 2,
+            ChoiceOfPromotablePiece::Queen => 3,
+            ChoiceOfPromotablePiece::Rook => 4,
+            ChoiceOfPromotablePiece::King => 5,
+            ChoiceOfPromotablePiece::Archbishop => 6,
+            ChoiceOfPromotablePiece::Chancellor => 7,
+            ChoiceOfPromotablePiece::Fairy(letter) => 8 + (letter as u8) % 26,
+        },
+        Some(Effect::Drop(piece)) => 34 + (piece_code(piece) % 30) as u8,
+        _ => 0,
+    }
+}
+
+/// What the table knows about a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<TableMove>,
+    /// Search that stored the entry, wrapping around
+    pub age: u8,
+}
+
+// Layout of the data of an entry: the move, whether there's a move, the score, the depth, the
+// bound and the age
+const MOVE_BITS: u32 = 26;
+const HAS_MOVE_SHIFT: u32 = 26;
+const SCORE_SHIFT: u32 = 27;
+const SCORE_BITS: u32 = 20;
+const DEPTH_SHIFT: u32 = 47;
+const BOUND_SHIFT: u32 = 55;
+const AGE_SHIFT: u32 = 57;
+
+impl Entry {
+    fn pack(self) -> u64 {
+        let (best_move, has_move) = match self.best_move {
+            Some(mov) => (mov.pack(), 1),
+            None => (0, 0),
+        };
+        let score = (self.score as u64) & ((1 << SCORE_BITS) - 1);
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        best_move
+            | has_move << HAS_MOVE_SHIFT
+            | score << SCORE_SHIFT
+            | u64::from(self.depth) << DEPTH_SHIFT
+            | bound << BOUND_SHIFT
+            | u64::from(self.age) << AGE_SHIFT
+    }
+
+    /// Empty slots are all zeroes, which has no bound
+    fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> BOUND_SHIFT) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        // The score is signed, its sign bit is extended back
+        let score = ((data >> SCORE_SHIFT) & ((1 << SCORE_BITS) - 1)) as i32;
+        let score = (score << (32 - SCORE_BITS)) >> (32 - SCORE_BITS);
+        let has_move = (data >> HAS_MOVE_SHIFT) & 1 == 1;
+        Some(Entry {
+            depth: (data >> DEPTH_SHIFT) as u8,
+            score,
+            bound,
+            best_move: has_move.then(|| TableMove::unpack(data & ((1 << MOVE_BITS) - 1))),
+            age: ((data >> AGE_SHIFT) as u8) % AGES,
+        })
+    }
+}
+
+/// The key is stored xored with the data, so that an entry written by two threads at once
+/// doesn't match any position
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key.load(Ordering::Relaxed) ^ data;
+        (key, data)
+    }
+
+    fn store(&self, key: u64, data: u64) {
+        self.key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// Remembers the result of searching a position, keyed by its Zobrist hash. It has a fixed
+/// size, and can be shared between threads searching at the same time
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// A table that takes about the given amount of megabytes
+    pub fn new(size_mb: usize) -> Self {
+        let entries = size_mb * 1024 * 1024 / ENTRY_BYTES;
+        TranspositionTable::with_buckets(entries / BUCKET_SIZE)
+    }
+
+    /// There's always at least one bucket
+    pub(crate) fn with_buckets(buckets: usize) -> Self {
+        let slots = (0..buckets.max(1) * BUCKET_SIZE)
+            .map(|_| Slot::default())
+            .collect();
+        TranspositionTable {
+            slots,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    /// Changes the size of the table, which forgets every entry
+    pub fn resize(&mut self, size_mb: usize) {
+        *self = TranspositionTable::new(size_mb);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.store(0, 0);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Entries from older searches are replaced first
+    pub fn new_search(&self) {
+        let age = (self.age.load(Ordering::Relaxed) + 1) % AGES;
+        self.age.store(age, Ordering::Relaxed);
+    }
+
+    fn get_age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    fn bucket(&self, hash: u64) -> &[Slot] {
+        let buckets = (self.slots.len() / BUCKET_SIZE) as u128;
+        let index = ((u128::from(hash) * buckets) >> 64) as usize;
+        &self.slots[index * BUCKET_SIZE..(index + 1) * BUCKET_SIZE]
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        self.bucket(hash).iter().find_map(|slot| {
+            let (key, data) = slot.load();
+            (key == hash).then(|| Entry::unpack(data)).flatten()
+        })
+    }
+
+    /// Overwrites the entry of the same position if there's one, otherwise the entry that is
+    /// worth the least: an empty one, then one from an older search, then the shallowest
+    pub fn store(
+        &self,
+        hash: u64,
+        depth: u8,
+        score: i32,
+        bound: Bound,
+        best_move: Option<TableMove>,
+    ) {
+        let age = self.get_age();
+        let bucket = self.bucket(hash);
+
+        let existing = bucket.iter().find(|slot| slot.load().0 == hash);
+        let (slot, best_move) = match existing {
+            Some(slot) => {
+                let old = Entry::unpack(slot.load().1);
+                // A deeper entry from this search is only replaced by an exact score
+                if old
+                    .is_some_and(|old| old.age == age && old.depth > depth && bound != Bound::Exact)
+                {
+                    return;
+                }
+                // A search without a best move keeps the older one
+                let best_move = best_move.or(old.and_then(|old| old.best_move));
+                (slot, best_move)
+            }
+            None => {
+                let worth = |slot: &Slot| match Entry::unpack(slot.load().1) {
+                    None => (0, 0),
+                    Some(entry) if entry.age != age => (1, entry.depth),
+                    Some(entry) => (2, entry.depth),
+                };
+                let slot = bucket.iter().min_by_key(|slot| worth(slot)).unwrap();
+                (slot, best_move)
+            }
+        };
+
+        let entry = Entry {
+            depth,
+            score,
+            bound,
+            best_move,
+            age,
+        };
+        slot.store(hash, entry.pack());
+    }
+
+    /// How full the table is in permill, counting only the entries of the current search
+    pub fn hashfull(&self) -> u32 {
+        let age = self.get_age();
+        let sample = &self.slots[..self.slots.len().min(HASHFULL_SAMPLE)];
+        let used = sample
+            .iter()
+            .filter_map(|slot| Entry::unpack(slot.load().1))
+            .filter(|entry| entry.age == age)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::PieceType;
+
+    fn mov(origin: (i8, i8), destination: (i8, i8), effect: Option<Effect>) -> Move {
+        Move::new(
+            Position::new(origin.0.into(), origin.1.into()),
+            Position::new(destination.0.into(), destination.1.into()),
+            effect,
+        )
+    }
+
+    #[test]
+    fn store_and_probe_test() {
+        let table = TranspositionTable::new(1);
+        let promotion = Effect::Promotion(Some(ChoiceOfPromotablePiece::Knight));
+        let best = mov((1, 6), (2, 7), Some(promotion));
+        table.store(42, 7, -99_000, Bound::Lower, Some(TableMove::new(best)));
+
+        let entry = table.probe(42).unwrap();
+        assert_eq!(entry.depth, 7);
+        assert_eq!(entry.score, -99_000);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert!(entry.best_move.unwrap().matches(best));
+        // Same squares, other piece
+        let queen = Effect::Promotion(Some(ChoiceOfPromotablePiece::Queen));
+        assert!(!entry
+            .best_move
+            .unwrap()
+            .matches(mov((1, 6), (2, 7), Some(queen))));
+
+        assert_eq!(table.probe(43), None);
+        let drop = mov((3, 3), (3, 3), Some(Effect::Drop(PieceType::Knight)));
+        table.store(43, 1, 5, Bound::Exact, Some(TableMove::new(drop)));
+        assert!(table.probe(43).unwrap().best_move.unwrap().matches(drop));
+    }
+
+    #[test]
+    fn replacement_test() {
+        // Every position goes to the only bucket
+        let table = TranspositionTable::with_buckets(1);
+        table.store(1, 10, 0, Bound::Exact, None);
+        table.store(2, 2, 0, Bound::Exact, None);
+        table.store(3, 5, 0, Bound::Exact, None);
+        // The shallowest one made room
+        assert!(table.probe(1).is_some());
+        assert!(table.probe(2).is_none());
+        assert!(table.probe(3).is_some());
+
+        // Old entries go first, however deep they were
+        table.new_search();
+        table.store(3, 1, 0, Bound::Exact, None);
+        table.store(4, 1, 0, Bound::Exact, None);
+        assert!(table.probe(1).is_none());
+        assert_eq!(table.probe(3).unwrap().depth, 1);
+        assert!(table.probe(4).is_some());
+    }
+
+    #[test]
+    fn keeps_best_move_test() {
+        let table = TranspositionTable::new(1);
+        let best = mov((4, 1), (4, 3), None);
+        table.store(7, 3, 10, Bound::Exact, Some(TableMove::new(best)));
+        table.new_search();
+        table.store(7, 4, 20, Bound::Upper, None);
+        let entry = table.probe(7).unwrap();
+        assert_eq!(entry.score, 20);
+        assert!(entry.best_move.unwrap().matches(best));
+    }
+
+    #[test]
+    fn hashfull_test() {
+        let table = TranspositionTable::with_buckets(500);
+        assert_eq!(table.hashfull(), 0);
+        for hash in 0..2000u64 {
+            table.store(
+                hash.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                1,
+                0,
+                Bound::Exact,
+                None,
+            );
+        }
+        assert!(table.hashfull() > 500);
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+        table.clear();
+        assert!(table.probe(0x9E37_79B9_7F4A_7C15).is_none());
+    }
+
+    #[test]
+    fn shared_between_threads_test() {
+        let table = TranspositionTable::new(1);
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..1000u64 {
+                        let hash = (thread << 32 | i).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                        table.store(hash, (i % 100) as u8, i as i32, Bound::Lower, None);
+                    }
+                });
+            }
+        });
+        // Entries either hold what was stored for their position or aren't found at all
+        for thread in 0..4u64 {
+            for i in 0..1000u64 {
+                let hash = (thread << 32 | i).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                if let Some(entry) = table.probe(hash) {
+                    assert_eq!(entry.score, i as i32);
+                    assert_eq!(entry.depth, (i % 100) as u8);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use board::{Board, Color, PieceType};
+
+// Kinds of features of a position, so that two of them never share a key
+const PIECE: u64 = 1;
+const TURN: u64 = 2;
+const POCKET: u64 = 3;
+const CHECKS: u64 = 4;
+const EN_PASSANT: u64 = 5;
+
+/// Random looking key of a feature of the position. Boards can have any size, so the keys are
+/// mixed from the feature instead of being kept in a table
+fn key(kind: u64, feature: u64) -> u64 {
+    // SplitMix64
+    let mut z = (kind << 60 ^ feature).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub(crate) fn piece_code(piece: PieceType) -> u64 {
+    match piece {
+        PieceType::Bishop => 1,
+        PieceType::King => 2,
+        PieceType::Knight => 3,
+        PieceType::Pawn => 4,
+        PieceType::Queen => 5,
+        PieceType::Rook => 6,
+        PieceType::Archbishop => 7,
+        PieceType::Chancellor => 8,
+        PieceType::Fairy(letter) => 16 + u64::from(letter),
+    }
+}
+
+fn color_code(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+        Color::Red => 2,
+        Color::Blue => 3,
+        Color::Yellow => 4,
+        Color::Green => 5,
+    }
+}
+
+/// Zobrist hash of the position: every piece with its square, whether it moved and whether it
+/// was promoted, the player to move, the en passant square, the pockets and the checks given.
+/// The move counters are left out, so the same position always has the same hash
+pub fn position_hash(board: &Board) -> u64 {
+    let mut hash = key(TURN, color_code(board.get_turn()));
+    if let Some(square) = board.get_en_passant() {
+        hash ^= key(
+            EN_PASSANT,
+            u64::from(square.x.0 as u8) << 8 | u64::from(square.y.0 as u8),
+        );
+    }
+
+    for piece in board.get_pieces() {
+        let position = piece.get_position();
+        let kind = piece.get_type();
+        // Only these pieces keep track of their first move, for castling and double steps
+        let tracks_moves = matches!(
+            kind,
+            PieceType::King | PieceType::Rook | PieceType::Pawn | PieceType::Fairy(_)
+        );
+        let moved = tracks_moves && piece.was_moved();
+        let feature = piece_code(kind) << 32
+            | color_code(piece.get_color()) << 24
+            | u64::from(position.x.0 as u8) << 16
+            | u64::from(position.y.0 as u8) << 8
+            | u64::from(moved) << 1
+            | u64::from(piece.was_promoted());
+        hash ^= key(PIECE, feature);
+    }
+
+    for color in [Color::White, Color::Black] {
+        let pocket = board.get_pocket(color);
+        let mut pieces = pocket.to_vec();
+        pieces.sort();
+        pieces.dedup();
+        for piece in pieces {
+            let count = pocket.iter().filter(|pocketed| **pocketed == piece).count() as u64;
+            hash ^= key(
+                POCKET,
+                piece_code(piece) << 32 | color_code(color) << 24 | count,
+            );
+        }
+
+        let checks = board.get_checks(color);
+        if checks > 0 {
+            hash ^= key(CHECKS, color_code(color) << 24 | u64::from(checks));
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::{Move, Position};
+
+    fn mov(origin: (i8, i8), destination: (i8, i8)) -> Move {
+        Move::new(
+            Position::new(origin.0.into(), origin.1.into()),
+            Position::new(destination.0.into(), destination.1.into()),
+            None,
+        )
+    }
+
+    #[test]
+    fn transposition_test() {
+        let start = Board::default();
+        let knights = [
+            mov((6, 0), (5, 2)),
+            mov((6, 7), (5, 5)),
+            mov((1, 0), (2, 2)),
+        ];
+        let mut first = start.clone();
+        for mov in knights {
+            first.execute_move(mov);
+        }
+
+        let mut second = start.clone();
+        for mov in [knights[2], knights[1], knights[0]] {
+            second.execute_move(mov);
+        }
+        assert_eq!(position_hash(&first), position_hash(&second));
+        assert_ne!(position_hash(&first), position_hash(&start));
+    }
+
+    #[test]
+    fn side_to_move_test() {
+        let white = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let black = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(position_hash(&white), position_hash(&black));
+
+        // The move counters don't matter
+        let later = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 12 40").unwrap();
+        assert_eq!(position_hash(&white), position_hash(&later));
+    }
+
+    #[test]
+    fn castling_rights_test() {
+        let castling = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let none = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+        assert_ne!(position_hash(&castling), position_hash(&none));
+    }
+}
@@ -1,26 +1,93 @@
 use raylib::prelude::*;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 
-use board::{Board, Color as PieceColor, Effect, Move, PieceType, Position};
+use super::opponent::{Computer, Strength};
+use board::{
+    Board, ChoiceOfPromotablePiece, Color as PieceColor, Effect, FourPlayerGame, Game, GameError,
+    Move, MoveRecord, PieceType, Position, Variant,
+};
 
 const TILE_SIZE: i32 = 120;
 
+/// Order of the pieces in a pocket, starting from the side of its owner
+const POCKET_PIECES: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// Games between two players, or four-player chess, which keeps its own score
+enum Match {
+    Duel(Game),
+    FourPlayer(FourPlayerGame),
+}
+
+impl Match {
+    fn get_board(&self) -> &Board {
+        match self {
+            Match::Duel(game) => game.get_board(),
+            Match::FourPlayer(game) => game.get_board(),
+        }
+    }
+
+    fn play(&mut self, mov: Move) -> Result<MoveRecord, GameError> {
+        match self {
+            Match::Duel(game) => game.play(mov),
+            Match::FourPlayer(game) => game.play(mov),
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        match self {
+            Match::Duel(game) => game.is_over(),
+            Match::FourPlayer(game) => game.is_over(),
+        }
+    }
+}
+
+/// Keys that choose the strength of the computer, from the weakest level
+const STRENGTH_KEYS: [KeyboardKey; 5] = [
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE,
+];
+
 pub struct GuiBoard {
-    board: Board,
+    game: Match,
 
     rl: RaylibHandle,
 
     thread: RaylibThread,
 
     images: BTreeMap<(PieceType, PieceColor), Texture2D>,
+
+    /// Plays one of the colors when playing against the computer
+    computer: Option<Computer>,
 }
 
 impl GuiBoard {
-    pub fn init() -> Self {
-        let (mut rl, thread) = raylib::init().size(960, 960).title("Hello, World").build();
-        let board = Board::default();
+    pub fn init(board: Board) -> Self {
+        let (width, height) = board.get_dimensions();
+        // Each pocket gets a column to the right of the board
+        let pockets = if board.get_rules().drops { 2 } else { 0 };
+        let (mut rl, thread) = raylib::init()
+            .size(
+                TILE_SIZE * (i32::from(width) + pockets),
+                TILE_SIZE * i32::from(height),
+            )
+            .title("Hello, World")
+            .build();
+        let game = if board.get_rules().variant == Variant::FourPlayer {
+            Match::FourPlayer(FourPlayerGame::with_board(board))
+        } else {
+            Match::Duel(Game::with_board(board, "White", "Black"))
+        };
         let mut images = BTreeMap::new();
 
         //TODO use an iterator and fold to make this cleaner. Maybe?
@@ -50,46 +117,152 @@ impl GuiBoard {
         images.insert((PieceType::Rook, PieceColor::White), w_r);
 
         GuiBoard {
-            board,
+            game,
             rl,
             thread,
             images,
+            computer: None,
         }
     }
+
+    /// The computer plays its color, and the human plays the other one
+    pub fn set_computer(&mut self, computer: Computer) {
+        assert!(
+            matches!(self.game, Match::Duel(_)),
+            "Four-player chess can't be played against the computer"
+        );
+        self.computer = Some(computer);
+    }
+
     pub fn start(&mut self) {
         let mut available_moves: Option<Vec<Move>> = None;
 
         while !self.rl.window_should_close() {
+            self.update_computer();
             let mut d = self.rl.begin_drawing(&self.thread);
+            let board = self.game.get_board();
 
-            draw_tiles(&mut d);
+            draw_tiles(&mut d, board);
             if let Some(ref moves) = available_moves {
-                draw_moves(&mut d, moves);
+                draw_moves(&mut d, moves, board);
+            }
+            // In Fog of war, only what the player to move can see is drawn. Against the computer
+            // it's always what the human can see
+            if board.get_rules().variant == Variant::FogOfWar {
+                let viewer = match self.computer {
+                    Some(ref computer) => !computer.get_color(),
+                    None => board.get_turn(),
+                };
+                draw_fog(&mut d, board, &board.visible_squares(viewer));
+                draw_pieces(&self.images, &mut d, &board.view_for(viewer));
+            } else {
+                draw_pieces(&self.images, &mut d, board);
+            }
+            if board.get_rules().drops {
+                draw_pockets(&self.images, &mut d, board);
+            }
+            if let Match::FourPlayer(ref game) = self.game {
+                draw_scores(&mut d, game);
+            }
+            if let Some(ref mut computer) = self.computer {
+                if let Some(level) = STRENGTH_KEYS.iter().position(|key| d.is_key_pressed(*key)) {
+                    computer.set_strength(Strength::new(level as u8 + 1));
+                }
+                draw_computer(&mut d, computer);
             }
-            draw_pieces(&self.images, &mut d, &self.board);
 
-            if let Some(position) = get_clicked_tile(&d) {
-                if let Some(ref moves) = available_moves {
-                    let desired_move = moves.iter().find(|mov| mov.destination == position);
-                    if let Some(mov) = desired_move {
-                        self.board.execute_move(*mov);
+            // The computer's pieces can't be moved by the human
+            let human_turn = self
+                .computer
+                .as_ref()
+                .is_none_or(|computer| computer.get_color() != board.get_turn());
+            if !human_turn {
+                available_moves = None;
+                continue;
+            }
+            if let Some(position) = get_clicked_tile(&d, board) {
+                if let Some(piece) = get_pocket_piece(board, position) {
+                    let drops = board
+                        .get_legal_moves()
+                        .into_iter()
+                        .filter(|mov| mov.effect == Some(Effect::Drop(piece)))
+                        .collect();
+                    available_moves = Some(drops);
+                } else if let Some(ref moves) = available_moves {
+                    // Castling is chosen by clicking the rook when the king can also step there
+                    let desired_move = Move::select(moves, position);
+                    if let Some(mut mov) = desired_move {
+                        // TODO: Let the player choose the piece
+                        if let Some(Effect::Promotion(None)) = mov.effect {
+                            mov.effect =
+                                Some(Effect::Promotion(Some(ChoiceOfPromotablePiece::Queen)));
+                        }
+                        // Moves come from the board, so they can only fail once the game is over
+                        let _ = self.game.play(mov);
                         available_moves = None;
                     } else {
-                        available_moves = self.board.get_moves_from(position);
+                        available_moves = self.game.get_board().get_moves_from(position);
                     };
                 } else {
-                    available_moves = self.board.get_moves_from(position);
+                    available_moves = self.game.get_board().get_moves_from(position);
                 }
             }
         }
     }
+
+    /// Plays the move of the computer once it's found, and has it think when it's its turn
+    fn update_computer(&mut self) {
+        let Some(ref mut computer) = self.computer else {
+            return;
+        };
+        match computer.poll() {
+            // UCI engines can answer with moves the game doesn't take, asking again would give
+            // the same move
+            Some(Ok(Some(mov))) => {
+                if let Err(err) = self.game.play(mov) {
+                    eprintln!("The computer stopped playing, its move was rejected: {err:?}");
+                    self.computer = None;
+                    return;
+                }
+            }
+            Some(Ok(None)) | None => (),
+            Some(Err(err)) => {
+                eprintln!("The computer stopped playing: {err}");
+                self.computer = None;
+                return;
+            }
+        }
+
+        let board = self.game.get_board();
+        if !self.game.is_over() && board.get_turn() == computer.get_color() {
+            computer.think(board);
+        }
+    }
+}
+
+/// Strength of the computer at the top left corner, and whether it's thinking
+fn draw_computer(rldraw: &mut RaylibDrawHandle, computer: &Computer) {
+    let level = computer.get_strength().get_level();
+    let mut text = format!("Level {level} (1-5)");
+    if computer.is_thinking() {
+        // The dots keep moving while the window is responsive
+        let dots = (rldraw.get_time() * 3.0) as usize % 4;
+        text = format!("{text}  Thinking{}", ".".repeat(dots));
+    }
+    let width = rldraw.measure_text(&text, 30);
+    rldraw.draw_rectangle(0, 0, width + 20, 40, Color::new(0, 0, 0, 160));
+    rldraw.draw_text(&text, 10, 5, 30, Color::GOLD);
 }
 
-fn draw_tiles(rldraw: &mut RaylibDrawHandle) {
-    for y in 0..=7 {
-        for x in 0..=7 {
+fn draw_tiles(rldraw: &mut RaylibDrawHandle, board: &Board) {
+    let (width, height) = board.get_dimensions();
+    for y in 0..i32::from(height) {
+        for x in 0..i32::from(width) {
+            let position = Position::new((x as i8).into(), (flip_y(board, y) as i8).into());
             // Interesting trick
-            let color = if (x + y) % 2 == 0 {
+            let color = if !board.is_inside(&position) {
+                Color::DARKGRAY
+            } else if (x + y) % 2 == 0 {
                 Color::WHITE
             } else {
                 Color::BLACK
@@ -108,13 +281,16 @@ fn draw_pieces(
 
     for piece in drawable_piece {
         let x: i32 = piece.get_position().x.0.into();
-        let y = piece.get_position().y.0;
-        let y: i32 = (7 - y).into();
+        let y: i32 = piece.get_position().y.0.into();
+        let y = flip_y(board, y);
 
         let color = piece.get_color();
         let type_of = piece.get_type();
 
-        let texture = images.get(&(type_of, color)).unwrap();
+        let Some((texture, tint)) = get_texture(images, type_of, color) else {
+            draw_missing(images, rldraw, type_of, color, x * TILE_SIZE, y * TILE_SIZE);
+            continue;
+        };
 
         rldraw.draw_texture(
             texture,
@@ -122,25 +298,201 @@ fn draw_pieces(
             // TODO: Clean this shit up
             x * TILE_SIZE - 15,
             y * TILE_SIZE - 18,
-            Color::WHITE,
+            tint,
         );
     }
 }
 
-fn get_clicked_tile(b: &RaylibDrawHandle) -> Option<Position> {
+/// Color the white pieces are tinted with for the players of four-player chess
+fn tint(color: PieceColor) -> Color {
+    match color {
+        PieceColor::White | PieceColor::Black => Color::WHITE,
+        PieceColor::Red => Color::RED,
+        PieceColor::Blue => Color::BLUE,
+        PieceColor::Yellow => Color::YELLOW,
+        PieceColor::Green => Color::GREEN,
+    }
+}
+
+/// Image of the piece and the tint to draw it with. The four-player colors reuse the white
+/// images
+fn get_texture(
+    images: &BTreeMap<(PieceType, PieceColor), Texture2D>,
+    piece: PieceType,
+    color: PieceColor,
+) -> Option<(&Texture2D, Color)> {
+    let image_color = match color {
+        PieceColor::Black => PieceColor::Black,
+        _ => PieceColor::White,
+    };
+    images
+        .get(&(piece, image_color))
+        .map(|texture| (texture, tint(color)))
+}
+
+/// Pieces that combine two others are drawn as both of them, side by side and smaller. Any
+/// other piece without an image gets its letter drawn instead
+fn draw_missing(
+    images: &BTreeMap<(PieceType, PieceColor), Texture2D>,
+    rldraw: &mut RaylibDrawHandle,
+    piece: PieceType,
+    color: PieceColor,
+    x: i32,
+    y: i32,
+) {
+    let parts = match piece {
+        PieceType::Archbishop => [PieceType::Bishop, PieceType::Knight],
+        PieceType::Chancellor => [PieceType::Rook, PieceType::Knight],
+        _ => return draw_letter(rldraw, piece, color, x, y),
+    };
+    // Same corrections as the full size pieces, scaled down
+    let offsets = [x - 12, x + 28];
+    for (part, offset) in parts.into_iter().zip(offsets) {
+        if let Some((texture, tint)) = get_texture(images, part, color) {
+            let position = Vector2::new(offset as f32, (y + 8) as f32);
+            rldraw.draw_texture_ex(texture, position, 0.0, 0.7, tint);
+        }
+    }
+}
+
+/// Pieces without an image, like fairy pieces, are drawn as a disc with their letter
+fn draw_letter(rldraw: &mut RaylibDrawHandle, piece: PieceType, color: PieceColor, x: i32, y: i32) {
+    let letter = match piece {
+        PieceType::Fairy(letter) => letter.to_ascii_uppercase(),
+        _ => '?',
+    };
+    let (fill, text) = match color {
+        PieceColor::Black => (Color::BLACK, Color::WHITE),
+        _ => (tint(color), Color::BLACK),
+    };
+    let center = TILE_SIZE / 2;
+    rldraw.draw_circle(x + center, y + center, TILE_SIZE as f32 / 3.0, text);
+    rldraw.draw_circle(x + center, y + center, TILE_SIZE as f32 / 3.0 - 4.0, fill);
+    let width = rldraw.measure_text(&letter.to_string(), 60);
+    rldraw.draw_text(
+        &letter.to_string(),
+        x + center - width / 2,
+        y + center - 30,
+        60,
+        text,
+    );
+}
+
+fn draw_fog(rldraw: &mut RaylibDrawHandle, board: &Board, visible: &HashSet<Position>) {
+    let (width, height) = board.get_dimensions();
+    for y in 0..height as i8 {
+        for x in 0..width as i8 {
+            if visible.contains(&Position::new(x.into(), y.into())) {
+                continue;
+            }
+            let y = flip_y(board, y.into());
+            rldraw.draw_rectangle(
+                TILE_SIZE * i32::from(x),
+                TILE_SIZE * y,
+                TILE_SIZE,
+                TILE_SIZE,
+                Color::DARKGRAY,
+            );
+        }
+    }
+}
+
+/// Points of each four-player chess player, in the cut out corner at the top left. Players that
+/// are out are crossed out
+fn draw_scores(rldraw: &mut RaylibDrawHandle, game: &FourPlayerGame) {
+    for (line, (color, score)) in game.standings().into_iter().enumerate() {
+        let y = 20 + 60 * line as i32;
+        let text = format!("{color:?}: {score}");
+        rldraw.draw_text(&text, 20, y, 40, tint(color));
+        if game.is_eliminated(color) {
+            let width = rldraw.measure_text(&text, 40);
+            rldraw.draw_rectangle(20, y + 18, width, 4, Color::BLACK);
+        }
+    }
+}
+
+/// Square of the screen where a piece of a pocket is shown. The pocket of white is in the first
+/// column to the right of the board and starts from the bottom, the one of black is in the
+/// second column and starts from the top
+fn pocket_square(board: &Board, color: PieceColor, index: usize) -> Position {
+    let (width, height) = board.get_dimensions();
+    let index = index as i8;
+    let (x, y) = match color {
+        PieceColor::Black => (width as i8 + 1, height as i8 - 1 - index),
+        // Only white and black play with drops
+        _ => (width as i8, index),
+    };
+    Position::new(x.into(), y.into())
+}
+
+fn draw_pockets(
+    images: &BTreeMap<(PieceType, PieceColor), Texture2D>,
+    rldraw: &mut RaylibDrawHandle,
+    board: &Board,
+) {
+    let (width, height) = board.get_dimensions();
+    rldraw.draw_rectangle(
+        TILE_SIZE * i32::from(width),
+        0,
+        TILE_SIZE * 2,
+        TILE_SIZE * i32::from(height),
+        Color::GRAY,
+    );
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        let pocket = board.get_pocket(color);
+        for (index, piece) in POCKET_PIECES.iter().enumerate() {
+            let count = pocket.iter().filter(|pocketed| *pocketed == piece).count();
+            if count == 0 {
+                continue;
+            }
+
+            let square = pocket_square(board, color, index);
+            let x = i32::from(square.x.0) * TILE_SIZE;
+            let y = flip_y(board, square.y.0.into()) * TILE_SIZE;
+            match images.get(&(*piece, color)) {
+                // Same corrections as the pieces on the board
+                Some(texture) => rldraw.draw_texture(texture, x - 15, y - 18, Color::WHITE),
+                None => draw_missing(images, rldraw, *piece, color, x, y),
+            }
+            rldraw.draw_text(&count.to_string(), x + 5, y + 5, 30, Color::BLACK);
+        }
+    }
+}
+
+/// The piece of the pocket of the player to move that was clicked, if there's any left
+fn get_pocket_piece(board: &Board, position: Position) -> Option<PieceType> {
+    if !board.get_rules().drops {
+        return None;
+    }
+    let color = board.get_turn();
+
+    POCKET_PIECES
+        .iter()
+        .enumerate()
+        .find(|(index, _)| pocket_square(board, color, *index) == position)
+        .map(|(_, piece)| *piece)
+        .filter(|piece| board.get_pocket(color).contains(piece))
+}
+
+/// The board grows upwards while the screen grows downwards
+fn flip_y(board: &Board, y: i32) -> i32 {
+    let (_, height) = board.get_dimensions();
+    i32::from(height) - 1 - y
+}
+
+fn get_clicked_tile(b: &RaylibDrawHandle, board: &Board) -> Option<Position> {
     let was_click = b.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
     if was_click == false {
         return None;
     };
 
     let mouse_position = b.get_mouse_position();
-    // TODO: Add constant
-    let x_tile = (mouse_position.x / 120.0).floor();
-    let y_tile = (mouse_position.y / 120.0).floor();
+    let x_tile = (mouse_position.x / TILE_SIZE as f32).floor();
+    let y_tile = (mouse_position.y / TILE_SIZE as f32).floor();
 
-    // TODO: Make this a function from-gui to board
     let x_tile: i8 = x_tile as i8;
-    let y_tile: i8 = 7 - y_tile as i8;
+    let y_tile: i8 = flip_y(board, y_tile as i32) as i8;
 
     Some(Position::new(x_tile.into(), y_tile.into()))
 }
@@ -154,18 +506,19 @@ fn translate_piece(piece_id: PieceType) -> String {
     }
 }
 
-fn draw_moves(rldraw: &mut RaylibDrawHandle, moves: &Vec<Move>) {
+fn draw_moves(rldraw: &mut RaylibDrawHandle, moves: &Vec<Move>, board: &Board) {
     for mov in moves {
         let destination = mov.destination;
         let x: i32 = destination.x.0.into();
         let y: i32 = destination.y.0.into();
-        let y = 7 - y;
+        let y = flip_y(board, y);
 
         let color = match mov.effect {
             None => Color::GREEN,
-            Some(Effect::Capture) => Color::RED,
+            Some(Effect::Capture) | Some(Effect::EnPassant(_)) => Color::RED,
             Some(Effect::Castling { .. }) => Color::YELLOW,
             Some(Effect::Promotion(_)) => Color::BLUE,
+            Some(Effect::Drop(_)) => Color::PURPLE,
         };
 
         rldraw.draw_rectangle(TILE_SIZE * x, TILE_SIZE * y, TILE_SIZE, TILE_SIZE, color);
@@ -1,3 +1,5 @@
 pub mod gui_board;
+pub mod opponent;
 
 pub use gui_board::GuiBoard;
+pub use opponent::{Computer, EngineKind, Strength};
@@ -0,0 +1,193 @@
+use board::{Board, Color as PieceColor, Move};
+use engine::{search_with_table, Limits, TranspositionTable};
+use uci_client::{ClientError, Engine, OptionKind, SearchLimits};
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Size of the transposition table of the built-in engine
+const TABLE_MB: usize = 16;
+/// Time the engine gets for each move, for every level of strength
+const TIME_PER_LEVEL: Duration = Duration::from_millis(400);
+
+/// Engine that plays against the human
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineKind {
+    /// The engine of the engine crate
+    BuiltIn,
+    /// Any executable that speaks UCI
+    Uci(PathBuf),
+}
+
+/// How well the computer plays, from 1 to 5. Stronger levels search deeper and think longer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Strength(u8);
+
+impl Strength {
+    pub const MIN: Strength = Strength(1);
+    pub const MAX: Strength = Strength(5);
+
+    /// Levels out of range are clamped
+    pub fn new(level: u8) -> Self {
+        Strength(level.clamp(Strength::MIN.0, Strength::MAX.0))
+    }
+
+    pub fn get_level(self) -> u8 {
+        self.0
+    }
+
+    fn limits(self) -> Limits {
+        Limits {
+            depth: Some(self.0),
+            time: Some(TIME_PER_LEVEL * u32::from(self.0)),
+            ..Limits::default()
+        }
+    }
+
+    /// UCI engines search much deeper in the same time
+    fn search_limits(self) -> SearchLimits {
+        SearchLimits {
+            depth: Some(2 * u32::from(self.0)),
+            movetime: Some(TIME_PER_LEVEL * u32::from(self.0)),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Strength(3)
+    }
+}
+
+/// A position to think about, with how well to play it
+struct Request {
+    board: Board,
+    strength: Strength,
+}
+
+/// None means that the computer has no legal moves
+type Reply = Result<Option<Move>, String>;
+
+/// The computer player. It thinks on a thread of its own, so that the window keeps drawing
+/// while it does
+pub struct Computer {
+    color: PieceColor,
+    strength: Strength,
+    requests: Sender<Request>,
+    replies: Receiver<Reply>,
+    thinking: bool,
+}
+
+impl Computer {
+    /// Starts the engine. UCI engines are started right away, so that a wrong path is found
+    /// before the game begins
+    pub fn spawn(
+        kind: EngineKind,
+        color: PieceColor,
+        strength: Strength,
+    ) -> Result<Computer, ClientError> {
+        let (requests, received) = mpsc::channel::<Request>();
+        let (sender, replies) = mpsc::channel();
+        match kind {
+            EngineKind::BuiltIn => {
+                thread::spawn(move || {
+                    let table = TranspositionTable::new(TABLE_MB);
+                    for Request { board, strength } in received {
+                        let result = search_with_table(&board, strength.limits(), &table);
+                        if sender.send(Ok(result.best_move)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            EngineKind::Uci(path) => {
+                let mut engine = Engine::spawn(&path, &[] as &[&str])?;
+                engine.new_game()?;
+                thread::spawn(move || {
+                    for Request { board, strength } in received {
+                        let reply = set_skill(&mut engine, strength)
+                            .and_then(|()| engine.best_move(&board, &strength.search_limits()))
+                            .map_err(|err| err.to_string());
+                        if sender.send(reply).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        }
+        Ok(Computer {
+            color,
+            strength,
+            requests,
+            replies,
+            thinking: false,
+        })
+    }
+
+    pub fn get_color(&self) -> PieceColor {
+        self.color
+    }
+
+    pub fn get_strength(&self) -> Strength {
+        self.strength
+    }
+
+    /// Takes effect from the next move
+    pub fn set_strength(&mut self, strength: Strength) {
+        self.strength = strength;
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.thinking
+    }
+
+    /// Starts looking for a move in the position, unless it's still thinking about another one
+    pub fn think(&mut self, board: &Board) {
+        if self.thinking {
+            return;
+        }
+        let request = Request {
+            board: board.clone(),
+            strength: self.strength,
+        };
+        self.thinking = self.requests.send(request).is_ok();
+    }
+
+    /// The move found, once the computer is done thinking
+    pub fn poll(&mut self) -> Option<Reply> {
+        if !self.thinking {
+            return None;
+        }
+        let reply = match self.replies.try_recv() {
+            Ok(reply) => reply,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("the engine stopped".to_string()),
+        };
+        self.thinking = false;
+        Some(reply)
+    }
+}
+
+/// Engines with a skill level option play weaker on the lower levels, as well as with less time
+fn set_skill(engine: &mut Engine, strength: Strength) -> Result<(), ClientError> {
+    let skill = engine
+        .get_options()
+        .iter()
+        .find_map(|option| match option.kind {
+            OptionKind::Spin { min, max, .. }
+                if option.name.eq_ignore_ascii_case("Skill Level") =>
+            {
+                let steps = i64::from(Strength::MAX.0 - Strength::MIN.0);
+                let level = i64::from(strength.0 - Strength::MIN.0);
+                Some(min + (max - min) * level / steps)
+            }
+            _ => None,
+        });
+    match skill {
+        Some(skill) => engine.set_option("Skill Level", Some(&skill.to_string())),
+        None => Ok(()),
+    }
+}
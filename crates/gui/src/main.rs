@@ -1,7 +1,78 @@
 pub mod gui_board;
-use gui_board::GuiBoard;
+use board::{Board, Color, Variant};
+use gui_board::{Computer, EngineKind, GuiBoard, Strength};
+
+use std::env;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
-    let mut board = GuiBoard::init();
+    // The first argument chooses the board: los-alamos, gardner, 10x8, 960, fen, file or the
+    // name of a variant, like atomic or four-player. 960 takes the number of the position
+    // (random if missing), fen takes the position and file takes the path to a variant definition
+    let mut args: Vec<String> = env::args().collect();
+    // Playing against the computer: --play white or --play black is the color of the human,
+    // --strength the level of the computer, from 1 to 5, and --engine the path to a UCI engine
+    // to play instead of the built-in one
+    let human = take_option(&mut args, "--play");
+    let strength = take_option(&mut args, "--strength");
+    let engine = take_option(&mut args, "--engine");
+    let board = match args.get(1).map(String::as_str) {
+        Some("los-alamos") => Board::los_alamos(),
+        Some("gardner") => Board::gardner(),
+        Some("10x8") => Board::ten_by_eight(),
+        Some("960") => {
+            let index = args
+                .get(2)
+                .and_then(|index| index.parse().ok())
+                .unwrap_or_else(|| {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default();
+                    (now.subsec_nanos() % 960) as u16
+                });
+            Board::chess960(index)
+        }
+        Some("fen") => {
+            let fen = args[2..].join(" ");
+            Board::from_fen(&fen).unwrap_or_else(|err| panic!("{err}"))
+        }
+        Some("file") => {
+            let path = args
+                .get(2)
+                .expect("Missing the path to the variant definition");
+            let definition = fs::read_to_string(path).unwrap_or_else(|err| panic!("{err}"));
+            Board::from_definition(&definition).unwrap_or_else(|err| panic!("{err}"))
+        }
+        Some(variant) => variant
+            .parse::<Variant>()
+            .map(Board::with_variant)
+            .unwrap_or_default(),
+        None => Board::default(),
+    };
+
+    let mut board = GuiBoard::init(board);
+    if let Some(human) = human {
+        let human = match human.as_str() {
+            "white" => Color::White,
+            "black" => Color::Black,
+            _ => panic!("Play as white or black, not {human}"),
+        };
+        let kind = engine.map_or(EngineKind::BuiltIn, |path| EngineKind::Uci(path.into()));
+        let strength = strength
+            .and_then(|level| level.parse().ok())
+            .map(Strength::new)
+            .unwrap_or_default();
+        let computer =
+            Computer::spawn(kind, !human, strength).unwrap_or_else(|err| panic!("{err}"));
+        board.set_computer(computer);
+    }
     board.start();
 }
+
+/// Removes the option and its value from the arguments, returning the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.remove(index);
+    (index < args.len()).then(|| args.remove(index))
+}
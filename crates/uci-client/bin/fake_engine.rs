@@ -0,0 +1,100 @@
+use board::{Board, Move};
+
+use std::env;
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::Duration;
+
+/// A UCI engine that plays the first legal move, for testing the client. Each argument changes
+/// how it behaves:
+/// --mute: never finishes introducing itself
+/// --stubborn: only answers go after stop
+/// --hang: never answers go
+/// --illegal: answers go with a move that isn't legal
+/// --late: answers the first go only a while after stop
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    let mut board = Board::default();
+    // The best move, while waiting for stop
+    let mut pending = None;
+    let mut late = flag("--late");
+
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("uci") if !flag("--mute") => {
+                println!("id name Fake Engine");
+                println!("id author Nobody");
+                println!("option name Hash type spin default 16 min 1 max 1024");
+                println!("option name Ponder type check default false");
+                println!("option name Style type combo default Normal var Solid var Normal");
+                println!("option name Clear Hash type button");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => println!("info string {line}"),
+            Some("position") => match position(words) {
+                Some(position) => board = position,
+                None => println!("info string invalid position"),
+            },
+            Some("go") if flag("--hang") => (),
+            Some("go") => {
+                let reply = reply(&board, flag("--illegal"));
+                if flag("--stubborn") || late {
+                    pending = Some(reply);
+                } else {
+                    println!("{reply}");
+                }
+            }
+            Some("stop") => {
+                if let Some(reply) = pending.take() {
+                    if late {
+                        thread::sleep(Duration::from_millis(500));
+                        late = false;
+                    }
+                    println!("{reply}");
+                }
+            }
+            Some("quit") => break,
+            _ => (),
+        }
+    }
+}
+
+/// position fen <fen> moves <move>..., or position startpos moves <move>...
+fn position<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Board> {
+    let mut board = match words.next()? {
+        "startpos" => Board::default(),
+        "fen" => {
+            let fields: Vec<_> = words.by_ref().take_while(|word| *word != "moves").collect();
+            Board::from_fen(&fields.join(" ")).ok()?
+        }
+        _ => return None,
+    };
+    for text in words.skip_while(|word| *word == "moves") {
+        let mov = board.parse_move(text).ok()?;
+        board.execute_move(mov);
+    }
+    Some(board)
+}
+
+/// Info about the position received, and the first legal move in alphabetical order
+fn reply(board: &Board, illegal: bool) -> String {
+    let mut moves: Vec<_> = board
+        .get_legal_moves()
+        .iter()
+        .map(Move::to_string)
+        .collect();
+    moves.sort();
+    let best = match moves.first() {
+        _ if illegal => "a1a1",
+        Some(best) => best,
+        None => "(none)",
+    };
+    format!(
+        "info string fen {}\ninfo depth 1 score cp 12 nodes {} pv {best}\nbestmove {best}",
+        board.to_fen(),
+        moves.len()
+    )
+}
@@ -0,0 +1,347 @@
+use crate::{BestMove, EngineOption, Info};
+
+use board::{Board, Move, NotationError};
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the engine has to answer uci and isready, and to send its move after the time it
+/// was given
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time given to searches that only the depth or the nodes limit, before they're stopped
+const UNTIMED_SEARCH: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The engine couldn't be started, or written to
+    Io(io::Error),
+    /// The engine didn't answer the command in time
+    Timeout(&'static str),
+    /// The engine closed its output, usually because it exited
+    Disconnected,
+    /// The engine doesn't have the option, or doesn't take the value for it
+    InvalidOption(String),
+    /// The engine sent a move that can't be played in the position
+    IllegalMove(NotationError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(err) => write!(f, "engine error: {err}"),
+            ClientError::Timeout(command) => write!(f, "engine didn't answer {command} in time"),
+            ClientError::Disconnected => write!(f, "engine exited"),
+            ClientError::InvalidOption(option) => write!(f, "invalid engine option: {option}"),
+            ClientError::IllegalMove(err) => write!(f, "engine sent an {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        ClientError::Io(err)
+    }
+}
+
+/// How long the engine can think, as sent with go. Without any limit the engine thinks until
+/// it's stopped, which happens after the timeout of the client
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// The go command with these limits
+    fn command(&self) -> String {
+        let mut command = "go".to_string();
+        let mut add = |name: &str, value: Option<u128>| {
+            if let Some(value) = value {
+                command.push_str(&format!(" {name} {value}"));
+            }
+        };
+        let millis = |time: Option<Duration>| time.map(|time| time.as_millis());
+        add("depth", self.depth.map(u128::from));
+        add("nodes", self.nodes.map(u128::from));
+        add("movetime", millis(self.movetime));
+        add("wtime", millis(self.wtime));
+        add("btime", millis(self.btime));
+        add("winc", millis(self.winc));
+        add("binc", millis(self.binc));
+        add("movestogo", self.movestogo.map(u128::from));
+        if command == "go" {
+            command.push_str(" infinite");
+        }
+        command
+    }
+
+    /// Time the engine may spend on the move
+    fn expected_time(&self, board: &Board) -> Duration {
+        let (time, increment) = match board.get_turn() {
+            board::Color::Black => (self.btime, self.binc),
+            _ => (self.wtime, self.winc),
+        };
+        let unlimited = self.depth.is_none() && self.nodes.is_none();
+        self.movetime
+            .or(time.map(|time| time + increment.unwrap_or_default()))
+            .unwrap_or(if unlimited {
+                Duration::ZERO
+            } else {
+                UNTIMED_SEARCH
+            })
+    }
+}
+
+/// An engine running in its own process, spoken to with the Universal Chess Interface
+pub struct Engine {
+    process: Child,
+    stdin: ChildStdin,
+    /// Lines written by the engine, read on another thread so that waiting can time out
+    lines: Receiver<String>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<EngineOption>,
+    timeout: Duration,
+    /// A search timed out, so its best move can still arrive
+    late_move: bool,
+}
+
+impl Engine {
+    /// Starts the executable and waits for it to introduce itself
+    pub fn spawn<S: AsRef<OsStr>>(
+        path: impl AsRef<OsStr>,
+        args: &[S],
+    ) -> Result<Engine, ClientError> {
+        Engine::spawn_with_timeout(path, args, DEFAULT_TIMEOUT)
+    }
+
+    /// Like spawn, with the time the engine has to answer each command
+    pub fn spawn_with_timeout<S: AsRef<OsStr>>(
+        path: impl AsRef<OsStr>,
+        args: &[S],
+        timeout: Duration,
+    ) -> Result<Engine, ClientError> {
+        let mut process = Command::new(path)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = process.stdin.take().expect("stdin is piped");
+        let stdout = process.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            process,
+            stdin,
+            lines,
+            name: None,
+            author: None,
+            options: Vec::new(),
+            timeout,
+            late_move: false,
+        };
+        engine.send("uci")?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = engine.receive(deadline, "uci")?;
+            if line == "uciok" {
+                break;
+            } else if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = Some(name.to_string());
+            } else if let Some(author) = line.strip_prefix("id author ") {
+                engine.author = Some(author.to_string());
+            } else if let Some(option) = EngineOption::parse(&line) {
+                engine.options.push(option);
+            }
+        }
+        Ok(engine)
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn get_options(&self) -> &[EngineOption] {
+        &self.options
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), ClientError> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// The next line of the engine, waiting until the deadline at most
+    fn receive(&self, deadline: Instant, command: &'static str) -> Result<String, ClientError> {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(wait) {
+            Ok(line) => Ok(line.trim().to_string()),
+            Err(RecvTimeoutError::Timeout) => Err(ClientError::Timeout(command)),
+            Err(RecvTimeoutError::Disconnected) => Err(ClientError::Disconnected),
+        }
+    }
+
+    /// Changes one of the options the engine announced. Buttons don't take a value
+    pub fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), ClientError> {
+        let option = self
+            .options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .filter(|option| option.accepts(value))
+            .ok_or_else(|| ClientError::InvalidOption(name.to_string()))?;
+        let command = match value {
+            Some(value) => format!("setoption name {} value {value}", option.name),
+            None => format!("setoption name {}", option.name),
+        };
+        self.send(&command)?;
+        self.wait_until_ready()
+    }
+
+    /// Waits for the engine to be done with the commands sent so far
+    pub fn wait_until_ready(&mut self) -> Result<(), ClientError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + self.timeout;
+        while self.receive(deadline, "isready")? != "readyok" {}
+        Ok(())
+    }
+
+    /// Tells the engine that the next position comes from another game
+    pub fn new_game(&mut self) -> Result<(), ClientError> {
+        self.send("ucinewgame")?;
+        self.wait_until_ready()
+    }
+
+    /// Throws away the best move of a search that timed out, if the engine sent it since. The
+    /// engine answers isready only once it's done with the stop sent before
+    fn discard_late_move(&mut self) -> Result<(), ClientError> {
+        if self.late_move {
+            self.wait_until_ready()?;
+            self.late_move = false;
+        }
+        Ok(())
+    }
+
+    /// Sends the position the board started from, followed by the moves played on it
+    pub fn set_position(&mut self, board: &Board) -> Result<(), ClientError> {
+        self.discard_late_move()?;
+        let history = board.get_history();
+        let mut start = board.clone();
+        while start.undo().is_some() {}
+
+        let mut command = format!("position fen {}", start.to_fen());
+        if !history.is_empty() {
+            command.push_str(" moves");
+            for record in history {
+                command.push_str(&format!(" {}", record.mov));
+            }
+        }
+        self.send(&command)
+    }
+
+    /// Starts searching the position that was set and waits for the best move. Every info
+    /// line is given to on_info as it arrives. The engine is told to stop once its time and the
+    /// timeout are over, and it has the timeout again to answer. Searches that only the depth
+    /// or the nodes limit get a minute
+    pub fn go(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        mut on_info: impl FnMut(&Info),
+    ) -> Result<BestMove, ClientError> {
+        self.discard_late_move()?;
+        self.send(&limits.command())?;
+        let mut deadline = Instant::now() + limits.expected_time(board) + self.timeout;
+        let mut stopped = false;
+        loop {
+            let line = match self.receive(deadline, "go") {
+                Err(ClientError::Timeout(_)) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    deadline = Instant::now() + self.timeout;
+                    continue;
+                }
+                Err(ClientError::Timeout(command)) => {
+                    self.late_move = true;
+                    return Err(ClientError::Timeout(command));
+                }
+                line => line?,
+            };
+
+            if let Some(best) = BestMove::parse(&line) {
+                return Ok(best);
+            } else if let Some(info) = Info::parse(&line) {
+                on_info(&info);
+            }
+        }
+    }
+
+    /// Asks the engine for its move in the position of the board. None means the engine has no
+    /// legal moves
+    pub fn best_move(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+    ) -> Result<Option<Move>, ClientError> {
+        self.set_position(board)?;
+        let best = self.go(board, limits, |_| ())?;
+        best.mov
+            .map(|mov| board.parse_move(&mov).map_err(ClientError::IllegalMove))
+            .transpose()
+    }
+}
+
+/// Asks the engine to quit, and ends it if it doesn't in time
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.process.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
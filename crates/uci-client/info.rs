@@ -0,0 +1,143 @@
+/// Evaluation sent by the engine, from the side of the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Moves to mate, negative when the engine is getting mated
+    Mate(i32),
+}
+
+/// What the engine reported about its search in an info line. Engines send only some of the
+/// fields in each line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// In milliseconds
+    pub time: Option<u64>,
+    pub hashfull: Option<u32>,
+    /// Moves in coordinate notation, like e2e4
+    pub pv: Vec<String>,
+    /// Free text, which takes the rest of the line
+    pub string: Option<String>,
+}
+
+impl Info {
+    /// Reads a line like info depth 5 score cp 31 nodes 1200 pv e2e4 e7e5. Lines that aren't
+    /// info lines are None
+    pub fn parse(line: &str) -> Option<Info> {
+        let mut words = line.split_whitespace();
+        if words.next()? != "info" {
+            return None;
+        }
+
+        let mut info = Info::default();
+        while let Some(word) = words.next() {
+            match word {
+                "depth" => info.depth = words.next().and_then(|value| value.parse().ok()),
+                "seldepth" => info.seldepth = words.next().and_then(|value| value.parse().ok()),
+                "nodes" => info.nodes = words.next().and_then(|value| value.parse().ok()),
+                "nps" => info.nps = words.next().and_then(|value| value.parse().ok()),
+                "time" => info.time = words.next().and_then(|value| value.parse().ok()),
+                "hashfull" => info.hashfull = words.next().and_then(|value| value.parse().ok()),
+                "score" => {
+                    let kind = words.next();
+                    let value = words.next().and_then(|value| value.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(value)) => Some(Score::Centipawns(value)),
+                        (Some("mate"), Some(value)) => Some(Score::Mate(value)),
+                        _ => None,
+                    };
+                }
+                // Both take the rest of the line
+                "pv" => {
+                    info.pv = words.by_ref().map(String::from).collect();
+                }
+                "string" => {
+                    let text: Vec<_> = words.by_ref().collect();
+                    info.string = Some(text.join(" "));
+                }
+                // Bounds, current move and everything else isn't kept
+                _ => (),
+            }
+        }
+        Some(info)
+    }
+}
+
+/// Answer of the engine to go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestMove {
+    /// None when the engine has no legal moves, which it sends as 0000 or (none)
+    pub mov: Option<String>,
+    /// The reply the engine expects
+    pub ponder: Option<String>,
+}
+
+impl BestMove {
+    /// Reads a line like bestmove e2e4 ponder e7e5. Other lines are None
+    pub fn parse(line: &str) -> Option<BestMove> {
+        let mut words = line.split_whitespace();
+        if words.next()? != "bestmove" {
+            return None;
+        }
+        let mov = words
+            .next()
+            .filter(|mov| !matches!(*mov, "0000" | "(none)"))
+            .map(String::from);
+        let ponder = match words.next() {
+            Some("ponder") => words.next().map(String::from),
+            _ => None,
+        };
+        Some(BestMove { mov, ponder })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_test() {
+        let info = Info::parse(
+            "info depth 12 seldepth 18 multipv 1 score cp -35 upperbound nodes 48213 nps 912000 \
+             hashfull 41 time 52 pv e7e5 g1f3 b8c6",
+        )
+        .unwrap();
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.score, Some(Score::Centipawns(-35)));
+        assert_eq!(info.nodes, Some(48213));
+        assert_eq!(info.nps, Some(912000));
+        assert_eq!(info.hashfull, Some(41));
+        assert_eq!(info.time, Some(52));
+        assert_eq!(info.pv, ["e7e5", "g1f3", "b8c6"]);
+
+        let mate = Info::parse("info depth 3 score mate -2 pv h7h6").unwrap();
+        assert_eq!(mate.score, Some(Score::Mate(-2)));
+        let string = Info::parse("info string NNUE enabled").unwrap();
+        assert_eq!(string.string.as_deref(), Some("NNUE enabled"));
+        assert_eq!(Info::parse("readyok"), None);
+    }
+
+    #[test]
+    fn best_move_test() {
+        assert_eq!(
+            BestMove::parse("bestmove e2e4 ponder e7e5"),
+            Some(BestMove {
+                mov: Some("e2e4".to_string()),
+                ponder: Some("e7e5".to_string()),
+            })
+        );
+        assert_eq!(
+            BestMove::parse("bestmove (none)"),
+            Some(BestMove {
+                mov: None,
+                ponder: None,
+            })
+        );
+        assert_eq!(BestMove::parse("info depth 1"), None);
+    }
+}
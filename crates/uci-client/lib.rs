@@ -0,0 +1,7 @@
+mod client;
+mod info;
+mod option;
+
+pub use client::{ClientError, Engine, SearchLimits};
+pub use info::{BestMove, Info, Score};
+pub use option::{EngineOption, OptionKind};
@@ -0,0 +1,162 @@
+/// Setting an engine announces when it starts, with the values it accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOption {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    Check {
+        default: bool,
+    },
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    /// One of a list of values
+    Combo {
+        default: String,
+        values: Vec<String>,
+    },
+    /// Does something once it's set, like clearing the hash
+    Button,
+    String {
+        default: String,
+    },
+}
+
+/// Words of the option line that start a new field
+const KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+
+impl EngineOption {
+    /// Reads a line like option name Hash type spin default 16 min 1 max 1024. Names and
+    /// values can have spaces
+    pub fn parse(line: &str) -> Option<EngineOption> {
+        let mut words = line.split_whitespace();
+        if words.next()? != "option" {
+            return None;
+        }
+
+        // Every keyword with the words after it
+        let mut fields: Vec<(&str, Vec<&str>)> = Vec::new();
+        for word in words {
+            match fields.last_mut() {
+                Some((_, values)) if !KEYWORDS.contains(&word) => values.push(word),
+                _ if KEYWORDS.contains(&word) => fields.push((word, Vec::new())),
+                _ => return None,
+            }
+        }
+        let field = |keyword: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| *key == keyword)
+                .map(|(_, values)| values.join(" "))
+        };
+        let number = |keyword: &str| field(keyword).and_then(|value| value.parse::<i64>().ok());
+
+        let name = field("name").filter(|name| !name.is_empty())?;
+        let kind = match field("type")?.as_str() {
+            "check" => OptionKind::Check {
+                default: field("default")? == "true",
+            },
+            "spin" => OptionKind::Spin {
+                default: number("default")?,
+                min: number("min")?,
+                max: number("max")?,
+            },
+            "combo" => OptionKind::Combo {
+                default: field("default")?,
+                values: fields
+                    .iter()
+                    .filter(|(key, _)| *key == "var")
+                    .map(|(_, values)| values.join(" "))
+                    .collect(),
+            },
+            "button" => OptionKind::Button,
+            "string" => OptionKind::String {
+                // <empty> stands for an empty string
+                default: field("default")
+                    .filter(|default| default != "<empty>")
+                    .unwrap_or_default(),
+            },
+            _ => return None,
+        };
+        Some(EngineOption { name, kind })
+    }
+
+    /// Whether the engine would take the value for this option
+    pub fn accepts(&self, value: Option<&str>) -> bool {
+        match (&self.kind, value) {
+            (OptionKind::Button, None) => true,
+            (OptionKind::Check { .. }, Some(value)) => matches!(value, "true" | "false"),
+            (OptionKind::Spin { min, max, .. }, Some(value)) => value
+                .parse::<i64>()
+                .is_ok_and(|value| (*min..=*max).contains(&value)),
+            (OptionKind::Combo { values, .. }, Some(value)) => values
+                .iter()
+                .any(|option| option.eq_ignore_ascii_case(value)),
+            (OptionKind::String { .. }, Some(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let hash = EngineOption::parse("option name Hash type spin default 16 min 1 max 1024");
+        assert_eq!(
+            hash,
+            Some(EngineOption {
+                name: "Hash".to_string(),
+                kind: OptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024,
+                },
+            })
+        );
+
+        let style = EngineOption::parse(
+            "option name Playing Style type combo default Normal var Solid var Normal var Risky",
+        )
+        .unwrap();
+        assert_eq!(style.name, "Playing Style");
+        assert_eq!(
+            style.kind,
+            OptionKind::Combo {
+                default: "Normal".to_string(),
+                values: vec!["Solid".into(), "Normal".into(), "Risky".into()],
+            }
+        );
+
+        let clear = EngineOption::parse("option name Clear Hash type button").unwrap();
+        assert_eq!(clear.kind, OptionKind::Button);
+        let path = EngineOption::parse("option name SyzygyPath type string default <empty>");
+        assert_eq!(
+            path.unwrap().kind,
+            OptionKind::String {
+                default: String::new(),
+            }
+        );
+        assert_eq!(EngineOption::parse("option type button"), None);
+    }
+
+    #[test]
+    fn accepts_test() {
+        let hash =
+            EngineOption::parse("option name Hash type spin default 16 min 1 max 1024").unwrap();
+        assert!(hash.accepts(Some("64")));
+        assert!(!hash.accepts(Some("4096")));
+        assert!(!hash.accepts(None));
+
+        let ponder = EngineOption::parse("option name Ponder type check default false").unwrap();
+        assert!(ponder.accepts(Some("true")));
+        assert!(!ponder.accepts(Some("yes")));
+    }
+}
@@ -0,0 +1,117 @@
+use board::Board;
+use std::time::Duration;
+use uci_client::{ClientError, Engine, OptionKind, Score, SearchLimits};
+
+const FAKE_ENGINE: &str = env!("CARGO_BIN_EXE_fake-engine");
+
+fn spawn(args: &[&str]) -> Result<Engine, ClientError> {
+    Engine::spawn_with_timeout(FAKE_ENGINE, args, Duration::from_millis(300))
+}
+
+#[test]
+fn handshake_test() {
+    let engine = spawn(&[]).unwrap();
+    assert_eq!(engine.get_name(), Some("Fake Engine"));
+    assert_eq!(engine.get_author(), Some("Nobody"));
+    let names: Vec<_> = engine
+        .get_options()
+        .iter()
+        .map(|option| option.name.as_str())
+        .collect();
+    assert_eq!(names, ["Hash", "Ponder", "Style", "Clear Hash"]);
+    assert_eq!(engine.get_options()[3].kind, OptionKind::Button);
+
+    assert!(matches!(
+        spawn(&["--mute"]),
+        Err(ClientError::Timeout("uci"))
+    ));
+    assert!(matches!(
+        Engine::spawn(FAKE_ENGINE.to_string() + "-missing", &[] as &[&str]),
+        Err(ClientError::Io(_))
+    ));
+}
+
+#[test]
+fn options_test() {
+    let mut engine = spawn(&[]).unwrap();
+    engine.set_option("hash", Some("64")).unwrap();
+    engine.set_option("Clear Hash", None).unwrap();
+    engine.set_option("Style", Some("Solid")).unwrap();
+    assert!(matches!(
+        engine.set_option("Hash", Some("100000")),
+        Err(ClientError::InvalidOption(_))
+    ));
+    assert!(matches!(
+        engine.set_option("Threads", Some("2")),
+        Err(ClientError::InvalidOption(_))
+    ));
+    engine.new_game().unwrap();
+}
+
+#[test]
+fn position_test() {
+    let mut engine = spawn(&[]).unwrap();
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    for text in ["e1g1", "e8d7", "a1a7"] {
+        board.execute_move(board.parse_move(text).unwrap());
+    }
+
+    engine.set_position(&board).unwrap();
+    let mut infos = Vec::new();
+    let best = engine
+        .go(&board, &SearchLimits::depth(1), |info| {
+            infos.push(info.clone())
+        })
+        .unwrap();
+    // The engine got the starting FEN and the moves, and ended up in the same position
+    let fen = infos[0].string.as_deref().unwrap();
+    assert_eq!(fen, format!("fen {}", board.to_fen()));
+    assert_eq!(infos[1].depth, Some(1));
+    assert_eq!(infos[1].score, Some(Score::Centipawns(12)));
+    assert_eq!(infos[1].pv.first(), best.mov.as_ref());
+
+    let mov = engine.best_move(&board, &SearchLimits::depth(1)).unwrap();
+    assert!(board.get_legal_moves().contains(&mov.unwrap()));
+}
+
+#[test]
+fn timeout_test() {
+    let board = Board::default();
+    let limits = SearchLimits::movetime(Duration::from_millis(50));
+
+    // Told to stop once the time is over, and it answers then
+    let mut stubborn = spawn(&["--stubborn"]).unwrap();
+    assert!(stubborn.best_move(&board, &limits).unwrap().is_some());
+
+    let mut hanging = spawn(&["--hang"]).unwrap();
+    assert!(matches!(
+        hanging.best_move(&board, &limits),
+        Err(ClientError::Timeout("go"))
+    ));
+}
+
+#[test]
+fn late_move_test() {
+    let mut board = Board::default();
+    let limits = SearchLimits::movetime(Duration::from_millis(50));
+    let mut late = spawn(&["--late"]).unwrap();
+    assert!(matches!(
+        late.best_move(&board, &limits),
+        Err(ClientError::Timeout("go"))
+    ));
+
+    // The move for the first position arrives meanwhile and isn't taken for the next one
+    board.execute_move(board.parse_move("e2e4").unwrap());
+    let mov = late.best_move(&board, &limits).unwrap().unwrap();
+    assert!(board.get_legal_moves().contains(&mov));
+}
+
+#[test]
+fn illegal_move_test() {
+    let mut engine = spawn(&["--illegal"]).unwrap();
+    let board = Board::default();
+    assert!(matches!(
+        engine.best_move(&board, &SearchLimits::depth(1)),
+        Err(ClientError::IllegalMove(_))
+    ));
+}
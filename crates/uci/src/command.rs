@@ -0,0 +1,268 @@
+use board::Color;
+use engine::Limits;
+
+use std::time::Duration;
+
+/// Time kept back for the engine to send its move, so that it doesn't lose on time
+pub(crate) const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+/// Moves the remaining time is split between when the GUI doesn't say how many are left
+const EXPECTED_MOVES: u32 = 30;
+
+/// Commands sent by the GUI. Anything else is ignored, as the protocol asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Uci,
+    Debug(bool),
+    IsReady,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    UciNewGame,
+    /// None means the starting position. The moves are in coordinate notation, like e2e4
+    Position {
+        fen: Option<String>,
+        moves: Vec<String>,
+    },
+    Go(Go),
+    Stop,
+    PonderHit,
+    Quit,
+}
+
+/// Limits of a search, as given by the go command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Go {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    /// Search until told to stop
+    pub infinite: bool,
+}
+
+impl Go {
+    /// Without any limit the search goes on until it's stopped
+    pub fn is_infinite(&self) -> bool {
+        self.infinite
+            || (self.depth.is_none()
+                && self.nodes.is_none()
+                && self.movetime.is_none()
+                && self.wtime.is_none()
+                && self.btime.is_none())
+    }
+
+    /// Limits of the search for the player to move. A fixed time per move wins over the clock
+    pub fn limits(&self, turn: Color) -> Limits {
+        let (time, increment) = match turn {
+            Color::Black => (self.btime, self.binc),
+            _ => (self.wtime, self.winc),
+        };
+        let time = if self.infinite {
+            None
+        } else {
+            self.movetime
+                .map(|movetime| movetime.saturating_sub(MOVE_OVERHEAD))
+                .or_else(|| time.map(|time| time_for_move(time, increment, self.movestogo)))
+        };
+        Limits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: time.map(|time| time.max(Duration::from_millis(1))),
+            ..Limits::default()
+        }
+    }
+}
+
+/// Share of the remaining time spent on a move, plus most of the increment
+pub(crate) fn time_for_move(
+    time: Duration,
+    increment: Option<Duration>,
+    movestogo: Option<u32>,
+) -> Duration {
+    let moves = movestogo.unwrap_or(EXPECTED_MOVES).max(1);
+    let increment = increment.unwrap_or_default();
+    let budget = time / moves + increment * 3 / 4;
+    budget.min(time.saturating_sub(MOVE_OVERHEAD))
+}
+
+impl Command {
+    /// Reads a line sent by the GUI. Unknown commands are None
+    pub fn parse(line: &str) -> Option<Command> {
+        let mut words = line.split_whitespace();
+        let command = match words.next()? {
+            "uci" => Command::Uci,
+            "debug" => Command::Debug(words.next() == Some("on")),
+            "isready" => Command::IsReady,
+            "setoption" => parse_setoption(words)?,
+            "ucinewgame" => Command::UciNewGame,
+            "position" => parse_position(words)?,
+            "go" => Command::Go(parse_go(words)),
+            "stop" => Command::Stop,
+            "ponderhit" => Command::PonderHit,
+            "quit" => Command::Quit,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// setoption name <name> [value <value>], where both can have spaces
+fn parse_setoption<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Command> {
+    if words.next()? != "name" {
+        return None;
+    }
+    let mut name = Vec::new();
+    let mut value = None::<Vec<&str>>;
+    for word in words {
+        match &mut value {
+            Some(value) => value.push(word),
+            None if word == "value" => value = Some(Vec::new()),
+            None => name.push(word),
+        }
+    }
+    Some(Command::SetOption {
+        name: name.join(" "),
+        value: value.map(|value| value.join(" ")),
+    })
+}
+
+/// position [startpos | fen <fen>] [moves <move>...]
+fn parse_position<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Command> {
+    let fen = match words.next()? {
+        "startpos" => None,
+        "fen" => {
+            let fields: Vec<_> = words.by_ref().take_while(|word| *word != "moves").collect();
+            Some(fields.join(" "))
+        }
+        _ => return None,
+    };
+    // take_while already went past moves after a FEN
+    let moves = words
+        .skip_while(|word| *word == "moves")
+        .map(String::from)
+        .collect();
+    Some(Command::Position { fen, moves })
+}
+
+fn parse_go<'a>(mut words: impl Iterator<Item = &'a str>) -> Go {
+    let mut go = Go::default();
+    while let Some(word) = words.next() {
+        match word {
+            "infinite" => {
+                go.infinite = true;
+                continue;
+            }
+            // Pondering isn't supported, the search goes on as usual
+            "ponder" => continue,
+            _ => (),
+        }
+        let Some(number) = words.next().and_then(|value| value.parse::<u64>().ok()) else {
+            continue;
+        };
+        let millis = Duration::from_millis(number);
+        match word {
+            "depth" => go.depth = Some(number.min(u8::MAX as u64) as u8),
+            "nodes" => go.nodes = Some(number),
+            "movetime" => go.movetime = Some(millis),
+            "wtime" => go.wtime = Some(millis),
+            "btime" => go.btime = Some(millis),
+            "winc" => go.winc = Some(millis),
+            "binc" => go.binc = Some(millis),
+            "movestogo" => go.movestogo = Some(number as u32),
+            _ => (),
+        }
+    }
+    go
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_test() {
+        assert_eq!(
+            Command::parse("position startpos moves e2e4 e7e5"),
+            Some(Command::Position {
+                fen: None,
+                moves: vec!["e2e4".to_string(), "e7e5".to_string()],
+            })
+        );
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(
+            Command::parse(&format!("position fen {fen} moves e1g1")),
+            Some(Command::Position {
+                fen: Some(fen.to_string()),
+                moves: vec!["e1g1".to_string()],
+            })
+        );
+        assert_eq!(
+            Command::parse(&format!("position fen {fen}")),
+            Some(Command::Position {
+                fen: Some(fen.to_string()),
+                moves: Vec::new(),
+            })
+        );
+        assert_eq!(Command::parse("position"), None);
+    }
+
+    #[test]
+    fn setoption_test() {
+        assert_eq!(
+            Command::parse("setoption name Hash value 64"),
+            Some(Command::SetOption {
+                name: "Hash".to_string(),
+                value: Some("64".to_string()),
+            })
+        );
+        assert_eq!(
+            Command::parse("setoption name Clear Hash"),
+            Some(Command::SetOption {
+                name: "Clear Hash".to_string(),
+                value: None,
+            })
+        );
+        assert_eq!(Command::parse("xyzzy"), None);
+    }
+
+    #[test]
+    fn go_test() {
+        let Some(Command::Go(go)) =
+            Command::parse("go wtime 60000 btime 30000 winc 1000 binc 1000 movestogo 20")
+        else {
+            panic!("Not a go command");
+        };
+        assert!(!go.is_infinite());
+        let white = go.limits(Color::White).time.unwrap();
+        let black = go.limits(Color::Black).time.unwrap();
+        assert_eq!(white, Duration::from_millis(3750));
+        assert_eq!(black, Duration::from_millis(2250));
+
+        let Some(Command::Go(go)) = Command::parse("go depth 5") else {
+            panic!("Not a go command");
+        };
+        assert_eq!(go.limits(Color::White).depth, Some(5));
+        assert_eq!(go.limits(Color::White).time, None);
+
+        let Some(Command::Go(go)) = Command::parse("go") else {
+            panic!("Not a go command");
+        };
+        assert!(go.is_infinite());
+    }
+
+    #[test]
+    fn low_on_time_test() {
+        // Never more than what's left on the clock
+        let time = time_for_move(
+            Duration::from_millis(100),
+            Some(Duration::from_secs(2)),
+            None,
+        );
+        assert_eq!(time, Duration::from_millis(70));
+    }
+}
@@ -0,0 +1,31 @@
+mod command;
+mod session;
+mod xboard;
+
+use session::{Output, Session};
+use xboard::XBoard;
+
+use std::io::{self, BufRead};
+use std::sync::Arc;
+
+/// Speaks the Universal Chess Interface, or the Chess Engine Communication Protocol of XBoard,
+/// over stdin and stdout, so that chess GUIs can play against the engine. The protocol is
+/// chosen by the first command of the GUI
+fn main() {
+    let output: Output = Arc::new(|line| println!("{line}"));
+    let mut lines = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .skip_while(|line| line.trim().is_empty())
+        .peekable();
+
+    let xboard = lines.peek().is_some_and(|line| line.trim() == "xboard");
+    if xboard {
+        let mut xboard = XBoard::new(output);
+        lines.take_while(|line| xboard.handle(line)).for_each(drop);
+    } else {
+        let mut session = Session::new(output);
+        lines.take_while(|line| session.handle(line)).for_each(drop);
+    }
+}
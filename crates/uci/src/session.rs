@@ -0,0 +1,375 @@
+use crate::command::{Command, Go};
+
+use board::{Board, Variant};
+use engine::{is_mate_score, search_with_report, SearchResult, TranspositionTable, MATE};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+pub(crate) const NAME: &str = "chess-uci";
+const AUTHOR: &str = "the chess authors";
+const DEFAULT_HASH_MB: usize = 16;
+const MAX_HASH_MB: usize = 4096;
+
+/// Where the lines for the GUI go. Searches write to it from their own thread
+pub type Output = Arc<dyn Fn(String) + Send + Sync>;
+
+/// A search running in the background
+struct Search {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// State of the engine between the commands of the GUI
+pub struct Session {
+    output: Output,
+    board: Board,
+    variant: Variant,
+    chess960: bool,
+    /// The last position couldn't be set up, so the board isn't the one the GUI has
+    rejected: bool,
+    table: Arc<TranspositionTable>,
+    search: Option<Search>,
+}
+
+impl Session {
+    pub fn new(output: Output) -> Self {
+        Session {
+            output,
+            board: Board::default(),
+            variant: Variant::Standard,
+            chess960: false,
+            rejected: false,
+            table: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            search: None,
+        }
+    }
+
+    fn send(&self, line: impl Into<String>) {
+        (self.output)(line.into());
+    }
+
+    /// Handles a line sent by the GUI. Returns false once the engine has to quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let Some(command) = Command::parse(line) else {
+            return true;
+        };
+        match command {
+            Command::Uci => self.identify(),
+            Command::IsReady => self.send("readyok"),
+            Command::SetOption { name, value } => self.set_option(&name, value.as_deref()),
+            Command::UciNewGame => {
+                self.stop();
+                self.table.clear();
+                self.board = Board::with_variant(self.variant);
+                self.rejected = false;
+            }
+            Command::Position { fen, moves } => self.set_position(fen.as_deref(), &moves),
+            Command::Go(go) => self.go(go),
+            Command::Stop => self.stop(),
+            Command::Quit => {
+                self.stop();
+                return false;
+            }
+            Command::Debug(_) | Command::PonderHit => (),
+        }
+        true
+    }
+
+    fn identify(&self) {
+        self.send(format!("id name {NAME}"));
+        self.send(format!("id author {AUTHOR}"));
+        self.send(format!(
+            "option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}"
+        ));
+        self.send("option name Clear Hash type button");
+        self.send("option name UCI_Chess960 type check default false");
+        let variants: String = playable_variants()
+            .map(|variant| format!(" var {variant}"))
+            .collect();
+        self.send(format!(
+            "option name UCI_Variant type combo default {}{variants}",
+            Variant::Standard
+        ));
+        self.send("uciok");
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        // Options can't change while a search is using them
+        self.stop();
+        match (name.to_ascii_lowercase().as_str(), value) {
+            ("hash", Some(value)) => match value.parse::<usize>() {
+                Ok(size) => {
+                    let size = size.clamp(1, MAX_HASH_MB);
+                    match Arc::get_mut(&mut self.table) {
+                        Some(table) => table.resize(size),
+                        None => self.table = Arc::new(TranspositionTable::new(size)),
+                    }
+                }
+                Err(_) => self.send(format!("info string invalid hash size: {value}")),
+            },
+            ("clear hash", _) => self.table.clear(),
+            ("uci_chess960", Some(value)) => self.chess960 = value == "true",
+            ("uci_variant", Some(value)) => match value.parse() {
+                Ok(variant) if playable_variants().any(|playable| playable == variant) => {
+                    self.variant = variant;
+                    self.board = Board::with_variant(variant);
+                    self.rejected = false;
+                }
+                _ => self.send(format!("info string unsupported variant: {value}")),
+            },
+            _ => self.send(format!("info string unknown option: {name}")),
+        }
+    }
+
+    /// Sets up the position and plays the moves on it. Playing stops at the first move that
+    /// isn't legal, and the position is rejected until the next one
+    fn set_position(&mut self, fen: Option<&str>, moves: &[String]) {
+        self.stop();
+        self.rejected = true;
+        let board = match fen {
+            None => Board::with_variant(self.variant),
+            Some(fen) => match Board::from_fen_with_rules(fen, self.variant.rules()) {
+                Ok(board) => board,
+                Err(err) => {
+                    self.send(format!("info string {err}"));
+                    return;
+                }
+            },
+        };
+        self.board = board;
+
+        for text in moves {
+            match self.board.parse_move(text) {
+                Ok(mov) => {
+                    self.board.execute_move(mov);
+                }
+                Err(err) => {
+                    self.send(format!("info string {err}"));
+                    return;
+                }
+            }
+        }
+        self.rejected = false;
+    }
+
+    /// Starts searching the current position in the background. The best move is sent once the
+    /// search ends, or after stop when the search is infinite
+    fn go(&mut self, go: Go) {
+        self.stop();
+        // Any move found would be for another position than the one the GUI has
+        if self.rejected {
+            self.send("bestmove 0000");
+            return;
+        }
+        let limits = go.limits(self.board.get_turn());
+        let stop = limits.stop.clone();
+        let infinite = go.is_infinite();
+
+        let board = self.board.clone();
+        let table = self.table.clone();
+        let output = self.output.clone();
+        let chess960 = self.chess960;
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            let started = Instant::now();
+            let result = search_with_report(&board, limits, &table, |result| {
+                output(info(result, started, &table, chess960));
+            });
+            // The GUI expects the best move only after stop when the search is infinite
+            while infinite && !thread_stop.load(Ordering::Relaxed) {
+                thread::park();
+            }
+
+            let best_move = match result.best_move {
+                Some(mov) => mov.to_uci(chess960),
+                None => "0000".to_string(),
+            };
+            match result.pv.get(1) {
+                Some(ponder) => output(format!(
+                    "bestmove {best_move} ponder {}",
+                    ponder.to_uci(chess960)
+                )),
+                None => output(format!("bestmove {best_move}")),
+            }
+        });
+        self.search = Some(Search { stop, handle });
+    }
+
+    /// Stops the search, if there's one, and waits for it to send its best move
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            search.handle.thread().unpark();
+            let _ = search.handle.join();
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Four-player chess can't be written in FEN
+fn playable_variants() -> impl Iterator<Item = Variant> {
+    Variant::ALL
+        .into_iter()
+        .filter(|variant| *variant != Variant::FourPlayer)
+}
+
+/// Score as sent to the GUI: centipawns, or moves to mate, negative when getting mated
+fn score(score: i32) -> String {
+    if is_mate_score(score) {
+        let plies = MATE - score.abs();
+        let moves = (plies + 1) / 2;
+        format!("mate {}", if score > 0 { moves } else { -moves })
+    } else {
+        format!("cp {score}")
+    }
+}
+
+fn info(
+    result: &SearchResult,
+    started: Instant,
+    table: &TranspositionTable,
+    chess960: bool,
+) -> String {
+    let millis = started.elapsed().as_millis().max(1);
+    let nps = result.nodes as u128 * 1000 / millis;
+    let pv: Vec<_> = result.pv.iter().map(|mov| mov.to_uci(chess960)).collect();
+    format!(
+        "info depth {} score {} nodes {} nps {nps} time {millis} hashfull {} pv {}",
+        result.depth,
+        score(result.score),
+        result.nodes,
+        table.hashfull(),
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn session() -> (Session, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let output = lines.clone();
+        let session = Session::new(Arc::new(move |line| output.lock().unwrap().push(line)));
+        (session, lines)
+    }
+
+    fn bestmove(lines: &Mutex<Vec<String>>) -> Vec<String> {
+        let lines = lines.lock().unwrap();
+        lines
+            .iter()
+            .filter(|line| line.starts_with("bestmove"))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn handshake_test() {
+        let (mut session, lines) = session();
+        assert!(session.handle("uci"));
+        assert!(session.handle("isready"));
+        let lines = lines.lock().unwrap();
+        assert!(lines[0].starts_with("id name"));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("option name Hash")));
+        assert_eq!(lines[lines.len() - 2], "uciok");
+        assert_eq!(lines[lines.len() - 1], "readyok");
+    }
+
+    #[test]
+    fn position_test() {
+        let (mut session, lines) = session();
+        session.handle("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(
+            session.board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        // Moves stop at the first illegal one
+        session.handle("position startpos moves e2e4 e2e4 e7e5");
+        assert!(lines.lock().unwrap()[0].contains("illegal move: e2e4"));
+        assert_eq!(
+            session.board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn en_passant_test() {
+        let (mut session, lines) = session();
+        session.handle("position startpos moves e2e4 a7a6 e4e5 d7d5 e5d6 a6a5");
+        assert!(lines.lock().unwrap().is_empty());
+        assert_eq!(
+            session.board.to_fen(),
+            "rnbqkbnr/1pp1pppp/3P4/p7/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 4"
+        );
+    }
+
+    #[test]
+    fn rejected_position_test() {
+        let (mut session, lines) = session();
+        session.handle("position startpos moves e2e4 e7e4");
+        // Searching what's left of the position would answer with a move for another one
+        session.handle("go depth 1");
+        assert_eq!(bestmove(&lines), ["bestmove 0000"]);
+
+        session.handle("position startpos moves e2e4");
+        session.handle("go depth 1");
+        while bestmove(&lines).len() < 2 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_ne!(bestmove(&lines)[1], "bestmove 0000");
+    }
+
+    #[test]
+    fn go_depth_test() {
+        let (mut session, lines) = session();
+        session.handle("position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        session.handle("go depth 2");
+        // The search ends by itself once it reaches the depth
+        while bestmove(&lines).is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(bestmove(&lines), ["bestmove a1a8"]);
+        let lines = lines.lock().unwrap();
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("info depth 1 score mate 1")));
+    }
+
+    #[test]
+    fn go_infinite_test() {
+        let (mut session, lines) = session();
+        session.handle("position startpos");
+        session.handle("go infinite");
+        thread::sleep(Duration::from_millis(50));
+        assert!(bestmove(&lines).is_empty());
+        session.handle("stop");
+        assert_eq!(bestmove(&lines).len(), 1);
+    }
+
+    #[test]
+    fn options_test() {
+        let (mut session, lines) = session();
+        session.handle("setoption name Hash value 1");
+        session.handle("setoption name UCI_Variant value crazyhouse");
+        assert!(session.board.get_rules().drops);
+        session.handle("setoption name UCI_Variant value bughouse");
+        session.handle("setoption name UCI_Chess960 value true");
+        session.handle("position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("unsupported variant"));
+    }
+}
@@ -0,0 +1,525 @@
+use crate::command::{time_for_move, MOVE_OVERHEAD};
+use crate::session::{Output, NAME};
+
+use board::{Board, Color, Variant};
+use engine::{is_mate_score, search_with_report, Limits, SearchResult, TranspositionTable, MATE};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DEFAULT_HASH_MB: usize = 16;
+
+/// Time control set with the level command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Level {
+    /// Moves to play before the clock is refilled, 0 for the whole game
+    moves: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+/// What the engine knows about the game, shared with the thread that's thinking
+struct State {
+    board: Board,
+    variant: Variant,
+    /// Color the engine plays, when it isn't in force mode
+    engine: Color,
+    /// The engine only keeps track of the moves, without playing any
+    force: bool,
+    level: Option<Level>,
+    /// Exact time to spend on every move, from st
+    move_time: Option<Duration>,
+    /// From sd
+    depth: Option<u8>,
+    /// Time left on the engine's clock, from time
+    clock: Option<Duration>,
+    /// Send thinking output
+    post: bool,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            board: Board::default(),
+            variant: Variant::Standard,
+            engine: Color::Black,
+            force: false,
+            level: None,
+            move_time: None,
+            depth: None,
+            clock: None,
+            post: false,
+        }
+    }
+
+    fn limits(&self) -> Limits {
+        let time = self
+            .move_time
+            .map(|time| time.saturating_sub(MOVE_OVERHEAD))
+            .or_else(|| {
+                let level = self.level?;
+                let clock = self.clock.unwrap_or(level.base);
+                // Moves left until the clock is refilled
+                let played = self.board.get_fullmove_number() - 1;
+                let movestogo = (level.moves > 0).then(|| level.moves - played % level.moves);
+                Some(time_for_move(clock, Some(level.increment), movestogo))
+            });
+        Limits {
+            depth: self.depth,
+            time: time.map(|time| time.max(Duration::from_millis(1))),
+            ..Limits::default()
+        }
+    }
+
+    fn engine_to_move(&self) -> bool {
+        !self.force && self.board.get_turn() == self.engine && self.board.outcome().is_none()
+    }
+}
+
+/// The engine thinking about its move in the background
+struct Thinking {
+    stop: Arc<AtomicBool>,
+    /// The move found is thrown away instead of played
+    discard: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// State of the engine between the commands of an XBoard/WinBoard GUI, which speaks the Chess
+/// Engine Communication Protocol
+pub struct XBoard {
+    output: Output,
+    state: Arc<Mutex<State>>,
+    table: Arc<TranspositionTable>,
+    thinking: Option<Thinking>,
+}
+
+impl XBoard {
+    pub fn new(output: Output) -> Self {
+        XBoard {
+            output,
+            state: Arc::new(Mutex::new(State::new())),
+            table: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+            thinking: None,
+        }
+    }
+
+    fn send(&self, line: impl Into<String>) {
+        (self.output)(line.into());
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Handles a line sent by the GUI. Returns false once the engine has to quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "xboard" | "accepted" | "rejected" | "hard" | "easy" | "random" | "computer"
+            | "name" | "rating" | "ics" | "otim" | "" => (),
+            "protover" => self.send(features()),
+            // Time controls and thinking output stay, the depth limit goes away
+            "new" => {
+                self.cancel();
+                let mut state = self.state();
+                *state = State {
+                    level: state.level,
+                    move_time: state.move_time,
+                    post: state.post,
+                    ..State::new()
+                };
+                drop(state);
+                self.table.clear();
+            }
+            "variant" => {
+                self.cancel();
+                match variant_from_name(arguments) {
+                    Some(variant) => {
+                        let mut state = self.state();
+                        state.variant = variant;
+                        state.board = Board::with_variant(variant);
+                    }
+                    None => self.send(format!("Error (unsupported variant): {arguments}")),
+                }
+            }
+            "force" => {
+                self.cancel();
+                self.state().force = true;
+            }
+            "go" => {
+                self.cancel();
+                {
+                    let mut state = self.state();
+                    state.force = false;
+                    state.engine = state.board.get_turn();
+                }
+                self.think();
+            }
+            "playother" => {
+                self.cancel();
+                let mut state = self.state();
+                state.force = false;
+                state.engine = !state.board.get_turn();
+            }
+            "usermove" => self.user_move(arguments),
+            "level" => match parse_level(arguments) {
+                Some(level) => {
+                    let mut state = self.state();
+                    state.level = Some(level);
+                    state.move_time = None;
+                    state.clock = None;
+                }
+                None => self.send(format!("Error (invalid time control): {line}")),
+            },
+            "st" => match arguments.parse() {
+                Ok(seconds) => self.state().move_time = Some(Duration::from_secs(seconds)),
+                Err(_) => self.send(format!("Error (invalid time): {line}")),
+            },
+            "sd" => match arguments.parse() {
+                Ok(depth) => self.state().depth = Some(depth),
+                Err(_) => self.send(format!("Error (invalid depth): {line}")),
+            },
+            // In centiseconds
+            "time" => match arguments.parse::<u64>() {
+                Ok(time) => self.state().clock = Some(Duration::from_millis(time * 10)),
+                Err(_) => self.send(format!("Error (invalid time): {line}")),
+            },
+            "undo" => self.undo(1),
+            "remove" => self.undo(2),
+            "setboard" => {
+                self.cancel();
+                let mut state = self.state();
+                match Board::from_fen_with_rules(arguments, state.variant.rules()) {
+                    Ok(board) => state.board = board,
+                    Err(err) => {
+                        drop(state);
+                        self.send(format!("tellusererror Illegal position: {err}"));
+                    }
+                }
+            }
+            // The game is over, the engine waits for the next one
+            "result" => {
+                self.cancel();
+                self.state().force = true;
+            }
+            "ping" => self.send(format!("pong {arguments}")),
+            // Move now
+            "?" => self.stop(),
+            "post" => self.state().post = true,
+            "nopost" => self.state().post = false,
+            "quit" => {
+                self.cancel();
+                return false;
+            }
+            // Moves without usermove come from GUIs that didn't accept the feature
+            _ if self.state().board.parse_move(line).is_ok() => self.user_move(line),
+            _ => self.send(format!("Error (unknown command): {command}")),
+        }
+        true
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.cancel();
+        let mut state = self.state();
+        match state.board.parse_move(text) {
+            Ok(mov) => {
+                state.board.execute_move(mov);
+                if let Some(result) = game_result(&state.board) {
+                    drop(state);
+                    self.send(result);
+                    return;
+                }
+            }
+            Err(_) => {
+                drop(state);
+                self.send(format!("Illegal move: {text}"));
+                return;
+            }
+        }
+        drop(state);
+        self.think();
+    }
+
+    /// Takes back moves, without changing the color the engine plays
+    fn undo(&mut self, moves: usize) {
+        self.cancel();
+        let mut state = self.state();
+        for _ in 0..moves {
+            state.board.undo();
+        }
+    }
+
+    /// Starts thinking in the background if the engine is to move. The move found is played
+    /// and sent once the search ends
+    fn think(&mut self) {
+        let state = self.state();
+        if !state.engine_to_move() {
+            return;
+        }
+        let board = state.board.clone();
+        let limits = state.limits();
+        let post = state.post;
+        drop(state);
+
+        let stop = limits.stop.clone();
+        let discard = Arc::new(AtomicBool::new(false));
+        let thread_discard = discard.clone();
+        let shared = self.state.clone();
+        let table = self.table.clone();
+        let output = self.output.clone();
+        let handle = thread::spawn(move || {
+            let started = Instant::now();
+            let result = search_with_report(&board, limits, &table, |result| {
+                if post {
+                    output(thinking(result, started));
+                }
+            });
+            let Some(mov) = result.best_move else {
+                return;
+            };
+            if thread_discard.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let mut state = shared.lock().unwrap();
+            state.board.execute_move(mov);
+            output(format!("move {mov}"));
+            if let Some(result) = game_result(&state.board) {
+                output(result);
+            }
+        });
+        self.thinking = Some(Thinking {
+            stop,
+            discard,
+            handle,
+        });
+    }
+
+    /// Makes the engine play the best move it found so far
+    fn stop(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.stop.store(true, Ordering::Relaxed);
+            let _ = thinking.handle.join();
+        }
+    }
+
+    /// Stops thinking without playing a move
+    fn cancel(&mut self) {
+        if let Some(thinking) = &self.thinking {
+            thinking.discard.store(true, Ordering::Relaxed);
+        }
+        self.stop();
+    }
+}
+
+impl Drop for XBoard {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn features() -> String {
+    let variants: Vec<_> = Variant::ALL.into_iter().filter_map(variant_name).collect();
+    format!(
+        "feature myname=\"{NAME}\" ping=1 setboard=1 usermove=1 playother=1 sigint=0 sigterm=0 \
+         colors=0 variants=\"{}\" done=1",
+        variants.join(",")
+    )
+}
+
+/// Name of the variant in the protocol, if the engine can play it
+fn variant_name(variant: Variant) -> Option<&'static str> {
+    let name = match variant {
+        Variant::Standard => "normal",
+        Variant::Crazyhouse => "crazyhouse",
+        Variant::Atomic => "atomic",
+        Variant::Antichess => "giveaway",
+        Variant::ThreeCheck => "3check",
+        Variant::KingOfTheHill => "kingofthehill",
+        Variant::RacingKings => "racingkings",
+        Variant::Horde => "horde",
+        Variant::Capablanca => "capablanca",
+        Variant::Grand => "grand",
+        Variant::Bughouse | Variant::FogOfWar | Variant::FourPlayer => return None,
+    };
+    Some(name)
+}
+
+fn variant_from_name(name: &str) -> Option<Variant> {
+    Variant::ALL
+        .into_iter()
+        .find(|variant| variant_name(*variant) == Some(name))
+}
+
+/// level MPS BASE INC, where the base time is in minutes, or minutes:seconds, and the
+/// increment in seconds
+fn parse_level(arguments: &str) -> Option<Level> {
+    let mut words = arguments.split_whitespace();
+    let moves = words.next()?.parse().ok()?;
+    let base = words.next()?;
+    let seconds = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?
+        }
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    let increment: f64 = words.next()?.parse().ok()?;
+    if !increment.is_finite() || increment < 0.0 {
+        return None;
+    }
+    Some(Level {
+        moves,
+        base: Duration::from_secs(seconds),
+        increment: Duration::from_secs_f64(increment),
+    })
+}
+
+/// The result command the engine sends when the game is over, like 1-0 {Checkmate}
+fn game_result(board: &Board) -> Option<String> {
+    let (result, termination) = board.outcome()?;
+    Some(format!("{result} {{{termination:?}}}"))
+}
+
+/// Thinking output: depth, score in centipawns, time in centiseconds, nodes and the moves
+/// expected. Mates score 100000 plus the moves to mate
+fn thinking(result: &SearchResult, started: Instant) -> String {
+    let score = if is_mate_score(result.score) {
+        let moves = (MATE - result.score.abs() + 1) / 2;
+        result.score.signum() * (100_000 + moves)
+    } else {
+        result.score
+    };
+    let pv: Vec<_> = result.pv.iter().map(ToString::to_string).collect();
+    format!(
+        "{} {score} {} {} {}",
+        result.depth,
+        started.elapsed().as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xboard() -> (XBoard, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let output = lines.clone();
+        let xboard = XBoard::new(Arc::new(move |line| output.lock().unwrap().push(line)));
+        (xboard, lines)
+    }
+
+    /// Waits for the engine to send a line starting with the prefix
+    fn wait_for(lines: &Mutex<Vec<String>>, prefix: &str) -> String {
+        loop {
+            let found = lines
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|line| line.starts_with(prefix))
+                .cloned();
+            if let Some(line) = found {
+                return line;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn fen(xboard: &XBoard) -> String {
+        xboard.state().board.to_fen()
+    }
+
+    #[test]
+    fn features_test() {
+        let (mut xboard, lines) = xboard();
+        xboard.handle("xboard");
+        xboard.handle("protover 2");
+        xboard.handle("ping 7");
+        let lines = lines.lock().unwrap();
+        assert!(lines[0].starts_with("feature myname=\"chess-uci\""));
+        assert!(lines[0].contains("variants=\"normal,crazyhouse,atomic,giveaway,"));
+        assert!(lines[0].ends_with("done=1"));
+        assert_eq!(lines[1], "pong 7");
+    }
+
+    #[test]
+    fn reply_test() {
+        let (mut xboard, lines) = xboard();
+        xboard.handle("new");
+        xboard.handle("sd 1");
+        xboard.handle("usermove e2e4");
+        wait_for(&lines, "move");
+        xboard.stop();
+        assert_eq!(xboard.state().board.get_history().len(), 2);
+        assert_eq!(xboard.state().board.get_turn(), Color::White);
+
+        xboard.handle("usermove e2e5");
+        assert_eq!(wait_for(&lines, "Illegal"), "Illegal move: e2e5");
+    }
+
+    #[test]
+    fn force_and_undo_test() {
+        let (mut xboard, lines) = xboard();
+        xboard.handle("new");
+        xboard.handle("force");
+        for mov in ["e2e4", "e7e5", "g1f3", "b8c6"] {
+            xboard.handle(&format!("usermove {mov}"));
+        }
+        assert!(lines.lock().unwrap().is_empty());
+
+        xboard.handle("undo");
+        assert_eq!(
+            fen(&xboard),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        xboard.handle("remove");
+        assert_eq!(
+            fen(&xboard),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn setboard_and_go_test() {
+        let (mut xboard, lines) = xboard();
+        xboard.handle("new");
+        xboard.handle("force");
+        xboard.handle("setboard 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        xboard.handle("sd 2");
+        xboard.handle("post");
+        xboard.handle("go");
+        assert_eq!(wait_for(&lines, "move"), "move a1a8");
+        assert_eq!(wait_for(&lines, "1-0"), "1-0 {Checkmate}");
+        assert!(lines.lock().unwrap()[0].starts_with("1 100001 "));
+    }
+
+    #[test]
+    fn level_test() {
+        assert_eq!(
+            parse_level("40 5 0"),
+            Some(Level {
+                moves: 40,
+                base: Duration::from_secs(300),
+                increment: Duration::ZERO,
+            })
+        );
+        assert_eq!(
+            parse_level("0 2:30 1.5"),
+            Some(Level {
+                moves: 0,
+                base: Duration::from_secs(150),
+                increment: Duration::from_millis(1500),
+            })
+        );
+        assert_eq!(parse_level("40 five 0"), None);
+
+        let mut state = State::new();
+        state.level = parse_level("40 5 0");
+        state.clock = Some(Duration::from_secs(60));
+        assert_eq!(state.limits().time, Some(Duration::from_millis(1500)));
+    }
+}